@@ -1,5 +1,5 @@
 pub const HELP: &str = "\
-Usage: aoc2021 -d <day> [-p <part>] [-e] [-i <input>]
+Usage: aoc2021 -d <day> [-p <part>] [-e] [-i <input>] [-t] [-m] [-j] [--fps <fps>] [--record <file>] [--break <at>]... [--projection <axes>] [--dump-image <file>]
 
 The CLI arguments allowed.
 
@@ -8,6 +8,14 @@ Options:
   -p, --part        specifies the part
   -e, --example     use the day's example input from examples/
   -i, --input       specify an alternate input file
+  -t, --time        print how long parsing and solving took
+  -m, --mem         print allocation count, bytes allocated and peak memory
+  -j, --json        print results as JSON, one object per line
+      --fps         frames per second for visualizations (default 30, needs the visualize feature)
+      --record      save the visualization to a .cast, .gif or .png file instead of showing it
+      --break       pause day 8, 18 and 23 visualizations at an instruction or a condition like \"a > 5\"
+      --projection  how day 20's particles are drawn: xy, xz, yz or rotate (the default)
+      --dump-image  save the final grid of days 14, 21 and 22 to a .png or .ppm file
   -h, --help        display usage information
 ";
 
@@ -16,6 +24,7 @@ Error: -i/--input and -e/--example can't be used together.
 ";
 
 /// The CLI arguments allowed.
+#[derive(Default)]
 pub struct Args {
     /// specifies the day (255 runs all parts)
     pub day: u8,
@@ -25,6 +34,27 @@ pub struct Args {
     pub example: bool,
     /// specify an alternate input file
     pub input: Option<String>,
+    /// print how long parsing and solving took
+    pub time: bool,
+    /// print allocation count, bytes allocated and peak memory
+    pub mem: bool,
+    /// print results as JSON, one object per line
+    pub json: bool,
+    /// frames per second for visualizations
+    #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+    pub fps: u32,
+    /// file to record the visualization to
+    #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+    pub record: Option<String>,
+    /// breakpoints for register machine visualizations
+    #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+    pub breakpoints: Vec<String>,
+    /// how day 20's visualization projects the particles
+    #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+    pub projection: Option<String>,
+    /// file to save the final grid to
+    #[cfg(not(feature = "no_std"))]
+    pub dump_image: Option<String>,
 }
 
 pub fn parse_args() -> Result<Args, pico_args::Error> {
@@ -40,6 +70,19 @@ pub fn parse_args() -> Result<Args, pico_args::Error> {
         part: pargs.value_from_str(["-p", "--part"]).or(Ok(1))?,
         example: pargs.contains(["-e", "--example"]),
         input: pargs.opt_value_from_str(["-i", "--input"])?,
+        time: pargs.contains(["-t", "--time"]),
+        mem: pargs.contains(["-m", "--mem"]),
+        json: pargs.contains(["-j", "--json"]),
+        #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+        fps: pargs.value_from_str("--fps").or(Ok(30))?,
+        #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+        record: pargs.opt_value_from_str("--record")?,
+        #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+        breakpoints: pargs.values_from_str("--break")?,
+        #[cfg(all(feature = "visualize", not(feature = "no_std")))]
+        projection: pargs.opt_value_from_str("--projection")?,
+        #[cfg(not(feature = "no_std"))]
+        dump_image: pargs.opt_value_from_str("--dump-image")?,
     };
 
     if pargs.contains(["-e", "--example"]) && pargs.contains(["-i", "--input"]) {
@@ -0,0 +1,176 @@
+//! Cycle detection for puzzles that repeatedly apply a step function to some state until it
+//! repeats (day 6's memory banks, day 16's billion dances, ...).
+//!
+//! Two strategies are available:
+//!
+//!  - [`hashed`] remembers every state it has seen, so it needs `S: Hash` and O(start + len)
+//!    memory, but it hands back the full history so any state can be looked up directly.
+//!  - [`brent`] uses Brent's algorithm, which only ever holds two states at a time.  Getting a
+//!    state back out of it means re-running the step function with [`Cycle::nth`].
+
+use core::hash::Hash;
+#[cfg(any(test, not(feature = "no_std")))]
+use std::collections::HashMap;
+
+/// The shape of the sequence `x0, step(x0), step(step(x0)), ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Index of the first state that is part of the cycle.
+    pub start: usize,
+    /// Number of states in the cycle.
+    pub len: usize,
+}
+
+impl Cycle {
+    /// Number of steps taken before a state is seen for the second time.
+    pub fn first_repeat(&self) -> usize {
+        self.start + self.len
+    }
+
+    /// Map any state index onto the equivalent index within `0..start + len`.
+    pub fn index(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.len
+        }
+    }
+
+    /// Compute the `n`th state by stepping forward from `initial` no further than necessary.
+    pub fn nth<S>(&self, n: usize, mut initial: S, mut step: impl FnMut(&mut S)) -> S {
+        for _ in 0..self.index(n) {
+            step(&mut initial);
+        }
+
+        initial
+    }
+}
+
+/// Find the cycle by recording every state in a hash map.
+///
+/// Also returns every state from index `0` up to (but not including) the first repeat, so
+/// `history[cycle.index(n)]` is the `n`th state.
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn hashed<S: Clone + Eq + Hash>(initial: S, mut step: impl FnMut(&mut S)) -> (Cycle, Vec<S>) {
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut history = Vec::new();
+    let mut state = initial;
+
+    loop {
+        if let Some(&start) = seen.get(&state) {
+            let cycle = Cycle {
+                start,
+                len: history.len() - start,
+            };
+            return (cycle, history);
+        }
+
+        seen.insert(state.clone(), history.len());
+        history.push(state.clone());
+        step(&mut state);
+    }
+}
+
+/// Find the cycle with Brent's algorithm, holding at most two states at once.
+pub fn brent<S: Clone + Eq>(initial: S, mut step: impl FnMut(&mut S)) -> Cycle {
+    // find the cycle length by letting the hare run ahead in ever-doubling stretches
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    step(&mut hare);
+
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        step(&mut hare);
+        len += 1;
+    }
+
+    // with the hare exactly one cycle length ahead, they meet at the start of the cycle
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..len {
+        step(&mut hare);
+    }
+
+    let mut start = 0;
+    while tortoise != hare {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+
+    Cycle { start, len }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Day 6's redistribution, enough to reproduce its example.
+    fn redistribute(banks: &mut [u32; 4]) {
+        let len = banks.len();
+        let (mut i, &blocks) = banks
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, b)| b)
+            .unwrap();
+
+        banks[i] = 0;
+        for _ in 0..blocks {
+            i = (i + 1) % len;
+            banks[i] += 1;
+        }
+    }
+
+    #[test]
+    fn hashed_test() {
+        let (cycle, history) = hashed([0, 2, 7, 0], redistribute);
+
+        assert_eq!(cycle, Cycle { start: 1, len: 4 });
+        assert_eq!(cycle.first_repeat(), 5);
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[cycle.index(5)], [2, 4, 1, 2]);
+    }
+
+    #[test]
+    fn brent_test() {
+        let cycle = brent([0, 2, 7, 0], redistribute);
+
+        assert_eq!(cycle, Cycle { start: 1, len: 4 });
+        assert_eq!(cycle.nth(5, [0, 2, 7, 0], redistribute), [2, 4, 1, 2]);
+    }
+
+    #[test]
+    fn strategies_agree_test() {
+        for seed in 0..50u64 {
+            let step = |x: &mut u64| *x = (*x * *x + 1) % 1009;
+            let (hashed_cycle, history) = hashed(seed, step);
+            let brent_cycle = brent(seed, step);
+
+            assert_eq!(hashed_cycle, brent_cycle, "seed {seed}");
+
+            // jump far past the end of the recorded history
+            let n = 1_000_000_000;
+            assert_eq!(
+                history[hashed_cycle.index(n)],
+                brent_cycle.nth(n, seed, step)
+            );
+        }
+    }
+
+    #[test]
+    fn index_test() {
+        let cycle = Cycle { start: 3, len: 4 };
+
+        assert_eq!(cycle.index(2), 2);
+        assert_eq!(cycle.index(3), 3);
+        assert_eq!(cycle.index(7), 3);
+        assert_eq!(cycle.index(9), 5);
+    }
+}
@@ -24,48 +24,152 @@
 // 91212129 produces 9 because the only digit that matches the next one is the last digit, 9.
 // What is the solution to your captcha?
 
-type Model = (ModelPart1, ModelPart2);
-type ModelPart1 = Vec<u32>;
-type ModelPart2 = String;
-type Answer = u32;
+#[cfg(any(test, not(feature = "no_std")))]
+type Model = Vec<u8>;
+type Answer = u64;
 
+#[cfg(any(test, not(feature = "no_std")))]
 pub fn parse(input: String) -> Model {
-    (input.trim().chars().map(char_to_u32).collect(), input)
+    input
+        .trim()
+        .bytes()
+        .map(|b| digit(b).unwrap_or_else(|| panic!("\"{}\" isn't a digit", b as char)))
+        .collect()
 }
 
-pub fn part1((digits, _): Model) -> Answer {
-    let mut sum = 0;
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn part1(digits: Model) -> Answer {
+    captcha(&digits, 1)
+}
 
-    for pair in digits.windows(2) {
-        if pair[0] == pair[1] {
-            sum += pair[0];
-        }
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn part2(digits: Model) -> Answer {
+    captcha(&digits, digits.len() / 2)
+}
+
+/// Sum the digits that match the digit `offset` places further round the circular list.
+/// Offsets wrap, so any offset works, even one longer than the list.
+pub fn captcha(digits: &[u8], offset: usize) -> Answer {
+    matching(digits, offset, 0)
+}
+
+/// Part 1 without allocating, for the `no_std` build.  `None` if the input isn't all digits.
+pub fn part1_str(input: &str) -> Option<Answer> {
+    Some(matching(ascii_digits(input)?, 1, b'0'))
+}
+
+/// Part 2 without allocating, for the `no_std` build.  `None` if the input isn't all digits.
+pub fn part2_str(input: &str) -> Option<Answer> {
+    let digits = ascii_digits(input)?;
+    Some(matching(digits, digits.len() / 2, b'0'))
+}
+
+/// The input's ASCII digits, as long as there's nothing else but surrounding whitespace.
+fn ascii_digits(input: &str) -> Option<&[u8]> {
+    let digits = input.trim().as_bytes();
+    digits.iter().all(u8::is_ascii_digit).then_some(digits)
+}
+
+/// The sum of the items that equal the one `offset` places after them, wrapping round the end,
+/// each less `base` so that ASCII digits can be summed without converting them first.  Items
+/// below `base` count as 0, so callers check them first.
+fn matching(items: &[u8], offset: usize, base: u8) -> u64 {
+    if items.is_empty() {
+        return 0;
     }
 
-    if digits.first() == digits.last() {
-        sum += digits.first().unwrap();
+    // compare the list against itself rotated left by the offset, in two straight runs
+    let split = offset % items.len();
+    let rest = items.len() - split;
+    sum_equal(&items[..rest], &items[split..], base)
+        + sum_equal(&items[rest..], &items[..split], base)
+}
+
+/// Items compared at once.
+const LANES: usize = 32;
+/// Chunks summed in 16-bit lanes before they're added to the total, the most that can't overflow.
+const BATCH: usize = u16::MAX as usize / u8::MAX as usize;
+
+/// The sum of `a[i] - base` wherever `a[i] == b[i]`, comparing whole vectors at a time.  Whatever
+/// doesn't fill a vector, which is everything for short lists, is left to [`sum_equal_scalar`].
+fn sum_equal(a: &[u8], b: &[u8], base: u8) -> u64 {
+    use core::simd::prelude::*;
+
+    debug_assert_eq!(a.len(), b.len());
+    let (a_chunks, a_rest) = a.as_chunks::<LANES>();
+    let (b_chunks, b_rest) = b.as_chunks::<LANES>();
+    let bases = Simd::splat(base);
+
+    let mut sum = 0;
+    for (a_batch, b_batch) in a_chunks.chunks(BATCH).zip(b_chunks.chunks(BATCH)) {
+        let mut lanes = u16x32::splat(0);
+        for (x, y) in a_batch.iter().zip(b_batch) {
+            let (x, y) = (u8x32::from_array(*x), u8x32::from_array(*y));
+            let values = x.simd_eq(y).select(x.saturating_sub(bases), Simd::splat(0));
+            lanes += values.cast();
+        }
+        sum += u64::from(lanes.cast::<u32>().reduce_sum());
     }
 
-    sum
+    sum + sum_equal_scalar(a_rest, b_rest, base)
+}
+
+/// [`sum_equal`] a pair at a time, which it's checked against.
+fn sum_equal_scalar(a: &[u8], b: &[u8], base: u8) -> u64 {
+    a.iter()
+        .zip(b)
+        .filter(|(x, y)| x == y)
+        .map(|(&x, _)| u64::from(x.saturating_sub(base)))
+        .sum()
 }
 
-pub fn part2((_, input): Model) -> u16 {
-    // cut the input in half
-    let (p1, p2) = input.as_bytes().split_at(input.len() / 2);
+/// The part 1 captcha of a list of digits read from `reader`, which can be longer than would fit
+/// in memory.  Whitespace is skipped, and anything else that isn't a digit is an error.
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn captcha_read(mut reader: impl std::io::Read) -> std::io::Result<Answer> {
+    use std::io::{Error, ErrorKind};
+
+    let mut buf = vec![0; 64 * 1024];
+    let mut sum = 0;
+    let mut first = None;
+    let mut prev = None;
+
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
 
-    let mut sum: u16 = 0;
+        for &b in &buf[..len] {
+            if b.is_ascii_whitespace() {
+                continue;
+            }
+            let d = digit(b).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("\"{}\" isn't a digit", b.escape_ascii()),
+                )
+            })?;
 
-    for i in 0..p1.len() {
-        if p1[i] == p2[i] {
-            sum += (p1[i] * 2 - 96) as u16;
+            if prev == Some(d) {
+                sum += u64::from(d);
+            }
+            first.get_or_insert(d);
+            prev = Some(d);
         }
     }
 
-    sum
+    // the list is circular, so the last digit is followed by the first
+    if first.is_some() && first == prev {
+        sum += u64::from(prev.unwrap());
+    }
+    Ok(sum)
 }
 
-fn char_to_u32(c: char) -> u32 {
-    (c as u32).saturating_sub(48)
+fn digit(b: u8) -> Option<u8> {
+    b.is_ascii_digit().then(|| b - b'0')
 }
 
 #[cfg(test)]
@@ -100,4 +204,90 @@ mod tests {
     fn d1p2_input_test() {
         assert_eq!(part2(parse(INPUT.to_string())), 1292);
     }
+
+    #[test]
+    fn d1_str_test() {
+        assert_eq!(part1_str("91212129"), Some(9));
+        assert_eq!(part1_str(INPUT), Some(1393));
+        assert_eq!(part2_str("12131415"), Some(4));
+        assert_eq!(part2_str(INPUT), Some(1292));
+
+        assert_eq!(part1_str("aa"), None);
+        assert_eq!(part2_str("12 12"), None);
+        // long enough for the vector path
+        assert_eq!(part1_str(&"9".repeat(100)), Some(900));
+        assert_eq!(part1_str(&"/".repeat(100)), None);
+    }
+
+    #[test]
+    fn captcha_test() {
+        let digits = parse("123123".to_string());
+        assert_eq!(captcha(&digits, 3), 12);
+        assert_eq!(captcha(&digits, 9), 12);
+        assert_eq!(captcha(&digits, 6), 12);
+        assert_eq!(captcha(&digits, 0), 12);
+        assert_eq!(captcha(&digits, 1), 0);
+        assert_eq!(captcha(&[], 5), 0);
+
+        // nine million nines would overflow a u16 many times over
+        assert_eq!(captcha(&vec![9; 9_000_000], 1), 81_000_000);
+    }
+
+    #[test]
+    fn sum_equal_test() {
+        // a cheap generator, so the same lists are checked every run
+        let mut seed = 1u32;
+        let mut next = |n: u32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % n
+        };
+
+        for _ in 0..500 {
+            let len = next(2000) as usize;
+            // few distinct digits, so plenty of them match
+            let alphabet = 1 + next(10) as u8;
+            let items: Vec<u8> = (0..len)
+                .map(|_| b'0' + next(alphabet as u32) as u8)
+                .collect();
+            let (offset, base) = (next(5000) as usize, [0, b'0'][next(2) as usize]);
+
+            let split = match len {
+                0 => 0,
+                len => offset % len,
+            };
+            let (a, b) = (&items[..len - split], &items[split..]);
+            assert_eq!(
+                sum_equal(a, b, base),
+                sum_equal_scalar(a, b, base),
+                "{len} items, offset {offset}"
+            );
+        }
+
+        // enough of the largest values to overflow the 16-bit lanes if they weren't emptied
+        let items = vec![u8::MAX; LANES * BATCH * 3 + 5];
+        let expected = items.len() as u64 * u64::from(u8::MAX);
+        assert_eq!(sum_equal(&items, &items, 0), expected);
+        assert_eq!(sum_equal_scalar(&items, &items, 0), expected);
+    }
+
+    /// A reader that hands out one byte at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn captcha_read_test() {
+        assert_eq!(captcha_read(Trickle(b"91212129\n")).unwrap(), 9);
+        assert_eq!(captcha_read(Trickle(b"1122")).unwrap(), 3);
+        assert_eq!(captcha_read(Trickle(b"")).unwrap(), 0);
+        assert_eq!(captcha_read(INPUT.as_bytes()).unwrap(), 1393);
+        assert!(captcha_read(Trickle(b"12x3")).is_err());
+    }
 }
@@ -1,19 +1,69 @@
-//! A solution to day 10 year .
-//! https://adventofcode.com//day/10
+//! A solution to day 10 year 2017.
+//! https://adventofcode.com/2017/day/10
 
-type Model = u8;
+#![cfg(any(test, not(feature = "no_std")))]
+
+type Model = String;
 type Answer = String;
 
 pub fn parse(input: String) -> Model {
-    0
+    input.trim().to_string()
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    let lengths: Vec<usize> = input
+        .split(',')
+        .map(|n| n.trim().parse().unwrap())
+        .collect();
+    let list = knot(&lengths, 256, 1);
+    (list[0] as u32 * list[1] as u32).to_string()
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    knot_hash(&input)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The full knot hash of `input`, which day 14 builds its disk from.
+pub fn knot_hash(input: &str) -> [u8; 16] {
+    let lengths: Vec<usize> = input
+        .bytes()
+        .chain([17, 31, 73, 47, 23])
+        .map(usize::from)
+        .collect();
+    let sparse = knot(&lengths, 256, 64);
+
+    let mut dense = [0; 16];
+    for (byte, block) in dense.iter_mut().zip(sparse.chunks(16)) {
+        *byte = block.iter().fold(0, |acc, n| acc ^ n);
+    }
+    dense
+}
+
+/// Tie `rounds` rounds of knots in a circular list of `size` numbers.  Lengths longer than the
+/// list are invalid, and skipped without moving on or growing the skip size.
+fn knot(lengths: &[usize], size: usize, rounds: usize) -> Vec<u8> {
+    let mut list: Vec<u8> = (0..size).map(|n| n as u8).collect();
+    let (mut pos, mut skip) = (0, 0);
+
+    for _ in 0..rounds {
+        for &len in lengths {
+            if len > size {
+                continue;
+            }
+
+            // rotate so the knot starts at the front, rather than reversing around the wrap
+            list.rotate_left(pos);
+            list[..len].reverse();
+            list.rotate_right(pos);
+
+            pos = (pos + len + skip) % size;
+            skip += 1;
+        }
+    }
+    list
 }
 
 #[cfg(test)]
@@ -21,29 +71,29 @@ mod tests {
     use super::*;
 
     const INPUT: &str = include_str!("../input/d10");
-    const EXAMPLE: &str = include_str!("../examples/d10");
 
     #[test]
     fn d10p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(knot(&[3, 4, 1, 5], 5, 1), [3, 4, 2, 1, 0]);
+    }
+
+    #[test]
+    fn invalid_length_test() {
+        assert_eq!(knot(&[3, 6, 4, 1, 5, 300], 5, 1), [3, 4, 2, 1, 0]);
+        assert_eq!(knot(&[5, 0], 5, 2), knot(&[5, 0, 9], 5, 2));
     }
 
     #[test]
     fn d10p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), "19591");
     }
 
     #[test]
     fn d10p2_example_test() {
+        assert_eq!(part2(String::new()), "a2582a3a0e66e6e86e3812dcb672a272");
         assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
+            part2("AoC 2017".to_string()),
+            "33efeb34ea91902bb2f59c9920caa6cd"
         );
     }
 
@@ -51,7 +101,7 @@ mod tests {
     fn d10p2_input_test() {
         assert_eq!(
             part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            "62e2204d2ca4f4924f6e7a80f1288786"
         );
     }
 }
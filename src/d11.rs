@@ -1,19 +1,221 @@
-//! A solution to day 11 year .
-//! https://adventofcode.com//day/11
+//! A solution to day 11 year 2017.
+//! https://adventofcode.com/2017/day/11
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+type Model = Vec<Hex>;
+type Answer = u32;
 
 pub fn parse(input: String) -> Model {
-    0
+    input.trim().split(',').map(Hex::step).collect()
 }
 
+/// How far the child process ends up from the start.
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input).0
 }
 
+/// The furthest the child process ever got from the start.
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input).1
+}
+
+fn solve(steps: &[Hex]) -> (Answer, Answer) {
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        return draw::run(steps);
+    }
+
+    walk(steps).fold((0, 0), |(_, furthest), hex| {
+        (hex.distance(), furthest.max(hex.distance()))
+    })
+}
+
+/// Every hex visited, starting from the origin.
+fn walk(steps: &[Hex]) -> impl Iterator<Item = Hex> + '_ {
+    let mut hex = Hex::default();
+    std::iter::once(hex).chain(steps.iter().map(move |&step| {
+        hex = hex + step;
+        hex
+    }))
+}
+
+/// A hex in axial coordinates on a grid of flat-topped hexes: `q` counts columns to the east,
+/// and `r` runs from north to south along a column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Hex {
+    q: i32,
+    r: i32,
+}
+
+impl Hex {
+    /// The step in one of the six directions.
+    fn step(direction: &str) -> Self {
+        let (q, r) = match direction.trim() {
+            "n" => (0, -1),
+            "ne" => (1, -1),
+            "se" => (1, 0),
+            "s" => (0, 1),
+            "sw" => (-1, 1),
+            "nw" => (-1, 0),
+            _ => panic!("unknown direction \"{direction}\""),
+        };
+        Self { q, r }
+    }
+
+    /// The fewest steps from the origin.
+    fn distance(self) -> u32 {
+        (self.q.unsigned_abs() + self.r.unsigned_abs() + (self.q + self.r).unsigned_abs()) / 2
+    }
+}
+
+impl std::ops::Add for Hex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            q: self.q + other.q,
+            r: self.r + other.r,
+        }
+    }
+}
+
+#[cfg(feature = "visualize")]
+mod draw {
+    use super::{walk, Answer, Hex};
+    use crate::viz::{self, Color, Frame, Style};
+
+    /// The largest picture drawn before the walk is scaled down.
+    const WIDTH: usize = 78;
+    const HEIGHT: usize = 30;
+    /// Characters between neighbouring columns of hexes, which keeps them roughly the right shape.
+    const COLUMN: i32 = 3;
+    /// At most about this many frames while the route is drawn.
+    const FRAMES: usize = 300;
+
+    /// Walk the route like [`super::solve`], drawing it as it goes, then the shortest way back.
+    pub fn run(steps: &[Hex]) -> (Answer, Answer) {
+        let route: Vec<Hex> = walk(steps).collect();
+        let view = View::new(&route);
+        let every = (route.len() / FRAMES).max(1);
+
+        let mut furthest = Hex::default();
+        for (i, &hex) in route.iter().enumerate() {
+            if hex.distance() > furthest.distance() {
+                furthest = hex;
+            }
+            if viz::active() && i % every == 0 && i + 1 < route.len() {
+                let mut frame = view.draw(&route[..=i], furthest, &[]);
+                frame.status(format!(
+                    "step {i} of {}: {} away, furthest {}",
+                    steps.len(),
+                    hex.distance(),
+                    furthest.distance()
+                ));
+                viz::emit(frame);
+            }
+        }
+
+        let end = *route.last().unwrap();
+        let back = way_back(end);
+        let mut frame = view.draw(&route, furthest, &back);
+        frame.status(format!(
+            "ended {} steps away after {}, furthest {}",
+            end.distance(),
+            steps.len(),
+            furthest.distance()
+        ));
+        frame.status("S start  E end  F furthest  * shortest way back");
+        viz::emit(frame);
+
+        (end.distance(), furthest.distance())
+    }
+
+    /// The hexes on one shortest route from `hex` back to the origin.
+    fn way_back(mut hex: Hex) -> Vec<Hex> {
+        let directions = ["n", "ne", "se", "s", "sw", "nw"].map(Hex::step);
+        let mut path = Vec::new();
+        while hex != Hex::default() {
+            hex = directions
+                .iter()
+                .map(|&step| hex + step)
+                .min_by_key(|next| next.distance())
+                .unwrap();
+            path.push(hex);
+        }
+        path
+    }
+
+    /// Where hexes land on the screen.  Each column is offset by half a hex from its neighbours,
+    /// so rows are counted in half hexes, and the picture is shrunk by `scale` to fit.
+    struct View {
+        left: i32,
+        top: i32,
+        width: usize,
+        height: usize,
+        scale: i32,
+    }
+
+    impl View {
+        fn new(route: &[Hex]) -> Self {
+            let xs = route.iter().map(|hex| hex.q * COLUMN);
+            let ys = route.iter().map(|hex| 2 * hex.r + hex.q);
+            let (left, right) = (xs.clone().min().unwrap(), xs.max().unwrap());
+            let (top, bottom) = (ys.clone().min().unwrap(), ys.max().unwrap());
+
+            let (w, h) = ((right - left + 1) as usize, (bottom - top + 1) as usize);
+            let scale = w.div_ceil(WIDTH).max(h.div_ceil(HEIGHT)) as i32;
+            Self {
+                left,
+                top,
+                width: w.div_ceil(scale as usize),
+                height: h.div_ceil(scale as usize),
+                scale,
+            }
+        }
+
+        fn cell(&self, hex: Hex) -> (usize, usize) {
+            let x = (hex.q * COLUMN - self.left) / self.scale;
+            let y = (2 * hex.r + hex.q - self.top) / self.scale;
+            (x as usize, y as usize)
+        }
+
+        fn draw(&self, route: &[Hex], furthest: Hex, back: &[Hex]) -> Frame {
+            let mut frame = Frame::new(self.width, self.height);
+
+            // the empty grid, when there's room to see it
+            if self.scale == 1 {
+                for y in 0..self.height as i32 {
+                    for x in (0..self.width as i32).filter(|x| (x + self.left) % COLUMN == 0) {
+                        let (q, y2) = ((x + self.left) / COLUMN, y + self.top);
+                        if (y2 - q).rem_euclid(2) == 0 {
+                            frame.set(x as usize, y as usize, '·', Style::fg(Color::Gray));
+                        }
+                    }
+                }
+            }
+
+            let mut mark = |hex: Hex, ch: char, color: Color| {
+                let (x, y) = self.cell(hex);
+                frame.set(x, y, ch, Style::fg(color).bold());
+            };
+            for &hex in route {
+                mark(hex, '•', Color::Cyan);
+            }
+            for &hex in back {
+                mark(hex, '*', Color::Yellow);
+            }
+            let end = *route.last().unwrap();
+            mark(furthest, 'F', Color::Magenta);
+            mark(Hex::default(), 'S', Color::Green);
+            match back.is_empty() {
+                true => mark(end, '●', Color::Yellow),
+                false => mark(end, 'E', Color::Red),
+            }
+
+            frame
+        }
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +227,43 @@ mod tests {
 
     #[test]
     fn d11p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 3);
+        assert_eq!(part1(parse("ne,ne,ne".to_string())), 3);
+        assert_eq!(part1(parse("ne,ne,sw,sw".to_string())), 0);
+        assert_eq!(part1(parse("ne,ne,s,s".to_string())), 2);
     }
 
     #[test]
     fn d11p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 759);
     }
 
     #[test]
     fn d11p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 3);
+        assert_eq!(part2(parse("ne,ne,sw,sw".to_string())), 2);
     }
 
     #[test]
     fn d11p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 1501);
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn d11_draw_test() {
+        let (answers, frames) = crate::viz::capture(|| solve(&parse("ne,ne,s,sw".to_string())));
+        assert_eq!(answers, (1, 2));
+        assert_eq!(frames.len(), 5);
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            frames[4].to_plain(),
+            "·     F
+   •
+S     •
+   E
+ended 1 steps away after 4, furthest 2
+S start  E end  F furthest  * shortest way back
+"
         );
     }
 }
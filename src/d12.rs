@@ -1,6 +1,8 @@
 //! A solution to day 12 year .
 //! https://adventofcode.com//day/12
 
+#![cfg(any(test, not(feature = "no_std")))]
+
 type Model = u8;
 type Answer = String;
 
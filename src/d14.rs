@@ -1,19 +1,92 @@
-//! A solution to day 14 year .
-//! https://adventofcode.com//day/14
+//! A solution to day 14 year 2017.
+//! https://adventofcode.com/2017/day/14
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use crate::d10::knot_hash;
+use crate::graph::DisjointSet;
+use crate::image::{self, Image, Rgb};
+
+/// The disk's rows, with the leftmost square in the highest bit.
+type Model = Vec<u128>;
+type Answer = usize;
+
+const SIZE: usize = 128;
+/// Pixels per square in dumped images.
+const SCALE: usize = 4;
+const FREE: Rgb = [16, 16, 16];
+const USED: Rgb = [200, 200, 200];
 
 pub fn parse(input: String) -> Model {
-    0
+    let key = input.trim();
+    (0..SIZE)
+        .map(|row| u128::from_be_bytes(knot_hash(&format!("{key}-{row}"))))
+        .collect()
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    image::dump(|| draw(&input, None));
+    input.iter().map(|row| row.count_ones() as Answer).sum()
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    let (count, regions) = regions(&input);
+    image::dump(|| draw(&input, Some(&regions)));
+    count
+}
+
+fn used(disk: &Model, x: usize, y: usize) -> bool {
+    disk[y] >> (SIZE - 1 - x) & 1 == 1
+}
+
+/// The number of regions of adjacent used squares, and the region of each square, numbered in
+/// reading order.
+fn regions(disk: &Model) -> (usize, Vec<Option<usize>>) {
+    let mut set = DisjointSet::new(SIZE * SIZE);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            if !used(disk, x, y) {
+                continue;
+            }
+            if x + 1 < SIZE && used(disk, x + 1, y) {
+                set.union(y * SIZE + x, y * SIZE + x + 1);
+            }
+            if y + 1 < SIZE && used(disk, x, y + 1) {
+                set.union(y * SIZE + x, (y + 1) * SIZE + x);
+            }
+        }
+    }
+
+    let mut numbers = vec![None; SIZE * SIZE];
+    let mut count = 0;
+    let regions = (0..SIZE * SIZE)
+        .map(|i| {
+            if !used(disk, i % SIZE, i / SIZE) {
+                return None;
+            }
+            let root = set.find(i);
+            Some(*numbers[root].get_or_insert_with(|| {
+                count += 1;
+                count - 1
+            }))
+        })
+        .collect();
+
+    (count, regions)
+}
+
+/// The disk, with each region in its own colour if they've been found.
+fn draw(disk: &Model, regions: Option<&[Option<usize>]>) -> Image {
+    let mut palette = vec![FREE, USED];
+    palette.extend(image::distinct(254));
+
+    Image::from_grid(SIZE, SIZE, SCALE, palette, |x, y| {
+        match (used(disk, x, y), regions) {
+            (false, _) => 0,
+            (true, None) => 1,
+            (true, Some(regions)) => 2 + (regions[y * SIZE + x].unwrap() % 254) as u8,
+        }
+    })
 }
 
 #[cfg(test)]
@@ -25,33 +98,36 @@ mod tests {
 
     #[test]
     fn d14p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 8108);
     }
 
     #[test]
     fn d14p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 8230);
     }
 
     #[test]
     fn d14p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 1242);
     }
 
     #[test]
     fn d14p2_input_test() {
-        assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
-        );
+        assert_eq!(part2(parse(INPUT.to_string())), 1103);
+    }
+
+    #[test]
+    fn draw_test() {
+        let disk = parse(EXAMPLE.to_string());
+        let (_, regions) = regions(&disk);
+        let image = draw(&disk, Some(&regions));
+        assert_eq!((image.width, image.height), (SIZE * SCALE, SIZE * SCALE));
+
+        // the example's top left corner is ##.#.#.., with the first two squares in one region
+        let color = |x: usize| image.get(x * SCALE, 0).unwrap();
+        assert_eq!(color(0), 2);
+        assert_eq!(color(1), 2);
+        assert_eq!(color(2), 0);
+        assert_eq!(color(3), 3);
     }
 }
@@ -1,6 +1,8 @@
 //! A solution to day 16 year .
 //! https://adventofcode.com//day/16
 
+#![cfg(any(test, not(feature = "no_std")))]
+
 type Model = u8;
 type Answer = String;
 
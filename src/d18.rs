@@ -1,19 +1,91 @@
-//! A solution to day 18 year .
-//! https://adventofcode.com//day/18
+//! A solution to day 18 year 2017.
+//! https://adventofcode.com/2017/day/18
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use crate::vm::{Asm, Io, Machine, Queue, Registers, State};
+
+type Model = (Vec<Asm>, Registers);
+type Answer = i64;
 
 pub fn parse(input: String) -> Model {
-    0
+    let mut regs = Registers::new();
+    let program = Asm::parse_program(&input, &mut regs).unwrap();
+    (program, regs)
 }
 
-pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+/// The first sound recovered by a `rcv` with a non-zero register.
+pub fn part1((program, regs): Model) -> Answer {
+    let mut vm = Machine::new(program, regs);
+    let mut sound = Sound::default();
+
+    #[cfg(feature = "visualize")]
+    crate::trace::Tracer::new().run(&mut vm, &mut sound);
+    #[cfg(not(feature = "visualize"))]
+    vm.run(&mut sound);
+
+    sound
+        .recovered
+        .expect("the program ended without recovering a sound")
 }
 
-pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+/// The number of values program 1 sends once both programs are deadlocked or finished.
+pub fn part2((program, regs): Model) -> Answer {
+    let mut vms: Vec<Machine<Asm>> = (0..2)
+        .map(|id| {
+            let mut regs = regs.clone();
+            let p = regs.intern("p");
+            regs.set(p, id);
+            Machine::new(program.clone(), regs)
+        })
+        .collect();
+    let mut queues = [Queue::default(), Queue::default()];
+
+    // program 1 is the one being counted, so that's the one on screen
+    #[cfg(feature = "visualize")]
+    let mut tracer = crate::trace::Tracer::new();
+
+    loop {
+        let first = vms[0].run(&mut queues[0]);
+        #[cfg(feature = "visualize")]
+        let second = tracer.run(&mut vms[1], &mut queues[1]);
+        #[cfg(not(feature = "visualize"))]
+        let second = vms[1].run(&mut queues[1]);
+
+        let (a, b) = queues.split_at_mut(1);
+        a[0].input.extend(b[0].output.drain(..));
+        b[0].input.extend(a[0].output.drain(..));
+
+        // a halted program never reads again, whatever is still waiting for it
+        let stuck = |state, queue: &Queue| {
+            state == State::Halted || (state == State::Blocked && queue.input.is_empty())
+        };
+        if stuck(first, &queues[0]) && stuck(second, &queues[1]) {
+            return queues[1].sent as Answer;
+        }
+    }
+}
+
+/// Part 1's reading of `snd` and `rcv`: playing and recovering sounds.  The machine blocks once
+/// a sound is recovered, since that's all we want from it.
+#[derive(Debug, Default)]
+struct Sound {
+    last: i64,
+    recovered: Option<i64>,
+}
+
+impl Io for Sound {
+    fn send(&mut self, value: i64) {
+        self.last = value;
+    }
+
+    fn receive(&mut self, current: i64) -> Option<i64> {
+        if current == 0 {
+            return Some(current);
+        }
+        self.recovered = Some(self.last);
+        None
+    }
 }
 
 #[cfg(test)]
@@ -22,36 +94,31 @@ mod tests {
 
     const INPUT: &str = include_str!("../input/d18");
     const EXAMPLE: &str = include_str!("../examples/d18");
+    const EXAMPLE2: &str = include_str!("../examples/d18-2");
 
     #[test]
     fn d18p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 4);
     }
 
     #[test]
     fn d18p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 9423);
     }
 
     #[test]
     fn d18p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE2.to_string())), 3);
     }
 
     #[test]
     fn d18p2_input_test() {
-        assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
-        );
+        assert_eq!(part2(parse(INPUT.to_string())), 7620);
+    }
+
+    #[test]
+    fn d18p2_halted_test() {
+        // both programs finish with a value neither of them will ever read
+        assert_eq!(part2(parse("snd 1\nsnd 1\nrcv a".to_string())), 2);
     }
 }
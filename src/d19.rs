@@ -1,19 +1,211 @@
-//! A solution to day 19 year .
-//! https://adventofcode.com//day/19
+//! A solution to day 19 year 2017.
+//! https://adventofcode.com/2017/day/19
 
-type Model = u8;
+#![cfg(any(test, not(feature = "no_std")))]
+
+type Model = Diagram;
 type Answer = String;
 
 pub fn parse(input: String) -> Model {
-    0
+    Diagram::parse(&input)
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input).0
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input).1.to_string()
+}
+
+/// The letters the packet passes, in order, and the number of steps it takes.
+fn solve(diagram: &Diagram) -> (String, usize) {
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        return draw::run(diagram);
+    }
+
+    Packet::new(diagram).fold((String::new(), 0), |(mut letters, steps), (_, _, c)| {
+        if c.is_ascii_alphabetic() {
+            letters.push(c as char);
+        }
+        (letters, steps + 1)
+    })
+}
+
+/// The routing diagram, with every row padded to the same width.
+#[derive(Debug, Clone)]
+pub struct Diagram {
+    rows: Vec<Vec<u8>>,
+    width: usize,
+}
+
+impl Diagram {
+    fn parse(input: &str) -> Self {
+        let width = input.lines().map(str::len).max().unwrap_or(0);
+        let rows = input
+            .lines()
+            .map(|line| {
+                let mut row = line.as_bytes().to_vec();
+                row.resize(width, b' ');
+                row
+            })
+            .collect();
+        Self { rows, width }
+    }
+
+    /// The character at (x, y), or a space off the edge of the diagram.
+    fn at(&self, x: isize, y: isize) -> u8 {
+        match (usize::try_from(x), usize::try_from(y)) {
+            (Ok(x), Ok(y)) if x < self.width && y < self.rows.len() => self.rows[y][x],
+            _ => b' ',
+        }
+    }
+}
+
+/// Follows the path from the top of the diagram, yielding each square it passes through.
+struct Packet<'d> {
+    diagram: &'d Diagram,
+    x: isize,
+    y: isize,
+    dx: isize,
+    dy: isize,
+}
+
+impl<'d> Packet<'d> {
+    fn new(diagram: &'d Diagram) -> Self {
+        let start = diagram
+            .rows
+            .first()
+            .and_then(|row| row.iter().position(|&c| c == b'|'));
+        Self {
+            diagram,
+            // with no way in, start off the edge so there's nothing to follow
+            x: start.map_or(-1, |x| x as isize),
+            y: 0,
+            dx: 0,
+            dy: 1,
+        }
+    }
+}
+
+impl Iterator for Packet<'_> {
+    /// The square's position and character.
+    type Item = (usize, usize, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.diagram.at(self.x, self.y);
+        if c == b' ' {
+            return None;
+        }
+        let here = (self.x as usize, self.y as usize, c);
+
+        if c == b'+' {
+            // turn to whichever side the path carries on
+            let (dx, dy) = (self.dy, self.dx);
+            (self.dx, self.dy) = match self.diagram.at(self.x + dx, self.y + dy) {
+                b' ' => (-dx, -dy),
+                _ => (dx, dy),
+            };
+        }
+        self.x += self.dx;
+        self.y += self.dy;
+
+        Some(here)
+    }
+}
+
+#[cfg(feature = "visualize")]
+mod draw {
+    use super::{Diagram, Packet};
+    use crate::viz::{self, Color, Frame, Style};
+
+    /// The part of the diagram in view, in characters.
+    const VIEW_WIDTH: usize = 64;
+    const VIEW_HEIGHT: usize = 24;
+    /// Room for the letters collected so far.
+    const SIDEBAR: usize = 14;
+    /// At most about this many frames, plus one for each letter picked up.
+    const FRAMES: usize = 1500;
+
+    /// Follow the packet like [`super::solve`], drawing it as it goes.
+    pub fn run(diagram: &Diagram) -> (String, usize) {
+        let path: Vec<(usize, usize, u8)> = Packet::new(diagram).collect();
+        let every = (path.len() / FRAMES).max(1);
+
+        let mut visited = vec![vec![false; diagram.width]; diagram.rows.len()];
+        let mut letters = Vec::new();
+
+        for (step, &(x, y, c)) in path.iter().enumerate() {
+            visited[y][x] = true;
+            let letter = c.is_ascii_alphabetic();
+            if letter {
+                letters.push((c as char, step + 1));
+            }
+
+            if viz::active() && (step % every == 0 || letter || step + 1 == path.len()) {
+                let heading = match path.get(step + 1) {
+                    Some(&(nx, _, _)) if nx > x => "right",
+                    Some(&(nx, _, _)) if nx < x => "left",
+                    Some(&(_, ny, _)) if ny < y => "up",
+                    Some(_) => "down",
+                    None => "nowhere, it's reached the end",
+                };
+
+                let mut frame = draw(diagram, &visited, (x, y), &letters);
+                frame.status(format!("step {}, heading {heading}", step + 1));
+                viz::emit(frame);
+            }
+        }
+
+        (letters.iter().map(|&(c, _)| c).collect(), path.len())
+    }
+
+    /// The diagram around the packet, with the path so far lit up, and the letters in a sidebar.
+    fn draw(
+        diagram: &Diagram,
+        visited: &[Vec<bool>],
+        (px, py): (usize, usize),
+        letters: &[(char, usize)],
+    ) -> Frame {
+        let (width, height) = (
+            diagram.width.min(VIEW_WIDTH),
+            diagram.rows.len().min(VIEW_HEIGHT),
+        );
+        // keep the packet in the middle of the view, until it reaches an edge of the diagram
+        let left = px.saturating_sub(width / 2).min(diagram.width - width);
+        let top = py
+            .saturating_sub(height / 2)
+            .min(diagram.rows.len() - height);
+
+        let mut frame = Frame::new(width + 2 + SIDEBAR, height);
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (left + x, top + y);
+                let c = diagram.rows[dy][dx];
+                let style = match (c, visited[dy][dx]) {
+                    (b'A'..=b'Z', true) => Style::fg(Color::Green).bold(),
+                    (b'A'..=b'Z', false) => Style::fg(Color::White).bold(),
+                    (b'+', true) => Style::fg(Color::Magenta),
+                    (_, true) => Style::fg(Color::Cyan),
+                    (_, false) => Style::fg(Color::Gray),
+                };
+                frame.set(x, y, c as char, style);
+            }
+        }
+        frame.set(px - left, py - top, '●', Style::fg(Color::Yellow).bold());
+
+        let sidebar = width + 2;
+        frame.print(sidebar, 0, "letters", Style::default().bold());
+        // the most recent ones, if there are more than fit
+        let shown = letters.len().min(height.saturating_sub(1));
+        for (row, (c, step)) in letters[letters.len() - shown..].iter().enumerate() {
+            let text = format!("{c}  step {step}");
+            frame.print(sidebar, row + 1, &text, Style::fg(Color::Green));
+        }
+
+        frame
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +217,50 @@ mod tests {
 
     #[test]
     fn d19p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), "ABCDEF");
     }
 
     #[test]
     fn d19p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), "ITSZCJNMUO");
     }
 
     #[test]
     fn d19p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE.to_string())), "38");
     }
 
     #[test]
     fn d19p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), "17420");
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn d19_draw_test() {
+        let (answers, frames) = crate::viz::capture(|| solve(&parse(EXAMPLE.to_string())));
+        assert_eq!(answers, ("ABCDEF".to_string(), 38));
+        // small enough to draw every step
+        assert_eq!(frames.len(), 38);
+
+        assert_eq!(
+            frames[18].to_plain(),
+            "     |            letters
+     |  +--+      A  step 3
+     A  |  C      B  step 7
+ F---|----E|--+   C  step 17
+     |  |  ●  D
+     +B-+  +--+
+step 19, heading down
+"
+        );
+        assert_eq!(
+            frames[18].get(5, 5).unwrap().style.fg,
+            Some(crate::viz::Color::Magenta)
+        );
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            frames[18].get(11, 2).unwrap().style.fg,
+            Some(crate::viz::Color::Green)
         );
     }
 }
@@ -1,78 +1,263 @@
 //! A solution to day 2 year .
 //! https://adventofcode.com//day/2
 
-type Model = Vec<Vec<u16>>;
-type Answer = u16;
+use core::fmt;
+use core::hash::Hash;
+use core::ops::{Add, Div, Mul, Rem, Sub};
+use core::str::FromStr;
+#[cfg(any(test, not(feature = "no_std")))]
+use std::collections::HashSet;
 
+#[cfg(any(test, not(feature = "no_std")))]
+type Model = Vec<Vec<Answer>>;
+type Answer = u32;
+
+#[cfg(any(test, not(feature = "no_std")))]
 pub fn parse(input: String) -> Model {
-    input
-        .lines()
-        .map(|line| -> Vec<u16> {
-            line.split_whitespace()
-                .map(|num| num.parse::<u16>().unwrap())
-                .collect()
-        })
-        .collect()
+    parse_sheet(&input).unwrap_or_else(|err| panic!("{err}"))
 }
 
-pub fn part1(lines: Model) -> Answer {
-    let mut sum = 0;
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn part1(sheet: Model) -> Answer {
+    checksum(&sheet, min_max).unwrap_or_else(|err| panic!("{err}"))
+}
 
-    for line in lines {
-        let mut min = u16::MAX;
-        let mut max = u16::MIN;
-        for num in line {
-            if num < min {
-                min = num;
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn part2(sheet: Model) -> Answer {
+    checksum(&sheet, divisible).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// The unsigned integer types a spreadsheet can hold.  Pick one wide enough for the checksum, which
+/// is checked for overflow rather than wrapping.
+pub trait Int:
+    Copy
+    + Ord
+    + Hash
+    + FromStr
+    + TryFrom<usize>
+    + fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn isqrt(self) -> Self;
+}
+
+macro_rules! impl_int {
+    ($($t:ty)*) => {$(
+        impl Int for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
             }
-            if num > max {
-                max = num;
+
+            fn isqrt(self) -> Self {
+                <$t>::isqrt(self)
             }
         }
+    )*};
+}
+
+impl_int!(u8 u16 u32 u64 u128 usize);
 
-        if let Some(diff) = max.checked_sub(min) {
-            sum += diff
+#[cfg(any(test, not(feature = "no_std")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A cell that isn't a number of the sheet's type, counting rows and columns from 1.
+    NotANumber {
+        row: usize,
+        column: usize,
+        cell: String,
+    },
+    /// The checksum doesn't fit the sheet's type once this row is added.
+    Overflow { row: usize },
+}
+
+#[cfg(any(test, not(feature = "no_std")))]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotANumber { row, column, cell } => {
+                write!(f, "row {row}, column {column}: \"{cell}\" isn't a number")
+            }
+            Self::Overflow { row } => write!(f, "the checksum overflows at row {row}"),
         }
     }
+}
 
-    sum
+/// How the cells in a row are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Any run of spaces or tabs, as in the puzzle.
+    Whitespace,
+    /// One tab between each cell, so two tabs in a row leave an empty cell.
+    Tsv,
+    /// One comma between each cell.
+    Csv,
 }
 
-pub fn part2(lines: Model) -> Answer {
-    let mut sum = 0;
+impl Format {
+    /// Commas mean CSV, otherwise tabs mean TSV.
+    pub fn detect(input: &str) -> Self {
+        if input.contains(',') {
+            Self::Csv
+        } else if input.contains('\t') {
+            Self::Tsv
+        } else {
+            Self::Whitespace
+        }
+    }
 
-    // itertools version
-    // for line in lines {
-    //     for pair in line.iter().permutations(2) {
-    //         let div = pair[0] / pair[1];
-    //         let rem = pair[0] % pair[1];
-    //         if rem == 0 {
-    //             sum += div;
-    //         }
-    //     }
-    // }
+    #[cfg(any(test, not(feature = "no_std")))]
+    fn cells(self, line: &str) -> Box<dyn Iterator<Item = &str> + '_> {
+        match self {
+            Self::Whitespace => Box::new(line.split_whitespace()),
+            Self::Tsv => Box::new(line.split('\t').map(str::trim)),
+            Self::Csv => Box::new(line.split(',').map(str::trim)),
+        }
+    }
+}
 
-    for line in lines {
-        'line: for a in &line {
-            for b in &line {
-                if a == b {
-                    break;
-                }
-                let (div_a, rem_a) = (a / b, a % b);
-                let (div_b, rem_b) = (b / a, b % a);
-                if rem_a == 0 {
-                    sum += div_a;
-                    break 'line;
+/// Parse a spreadsheet, working out its [`Format`] from the input.  Blank lines are skipped.
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn parse_sheet<T: Int>(input: &str) -> Result<Vec<Vec<T>>, Error> {
+    parse_sheet_as(input, Format::detect(input))
+}
+
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn parse_sheet_as<T: Int>(input: &str, format: Format) -> Result<Vec<Vec<T>>, Error> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(row, line)| {
+            format
+                .cells(line)
+                .enumerate()
+                .map(|(column, cell)| {
+                    cell.parse().map_err(|_| Error::NotANumber {
+                        row: row + 1,
+                        column: column + 1,
+                        cell: cell.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Sum the value `row_value` finds in each row, skipping rows where it finds none.  The named
+/// strategies are [`min_max`] and [`divisible`], but any function of a row will do.
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn checksum<T: Int, R: AsRef<[T]>>(
+    sheet: &[R],
+    row_value: impl Fn(&[T]) -> Option<T>,
+) -> Result<T, Error> {
+    sheet
+        .iter()
+        .enumerate()
+        .try_fold(T::ZERO, |sum, (row, cells)| {
+            match row_value(cells.as_ref()) {
+                Some(value) => sum
+                    .checked_add(value)
+                    .ok_or(Error::Overflow { row: row + 1 }),
+                None => Ok(sum),
+            }
+        })
+}
+
+/// The difference between the largest and smallest values in the row.
+pub fn min_max<T: Int>(row: &[T]) -> Option<T> {
+    let min = row.iter().min()?;
+    let max = row.iter().max()?;
+    Some(*max - *min)
+}
+
+/// The quotient of the first pair in the row where one value evenly divides the other.  Zeros
+/// divide nothing, and are skipped.
+///
+/// Each value is checked against the divisors up to its square root, which are looked up in a
+/// set, unless the row is short enough that comparing every pair is quicker: O(n min(n, √max))
+/// rather than O(n²) for wide rows of small numbers.
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn divisible<T: Int>(row: &[T]) -> Option<T> {
+    let mut values: Vec<T> = row.iter().copied().filter(|&v| v != T::ZERO).collect();
+    values.sort_unstable();
+
+    // a repeated value divides itself
+    if values.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Some(T::ONE);
+    }
+
+    let root = *values.last()?;
+    let root = root.isqrt();
+    if T::try_from(values.len()).is_ok_and(|len| len <= root) {
+        return values.iter().enumerate().find_map(|(i, &b)| {
+            values[..i]
+                .iter()
+                .find(|&&a| b % a == T::ZERO)
+                .map(|&a| b / a)
+        });
+    }
+
+    let set: HashSet<T> = values.iter().copied().collect();
+    values.iter().find_map(|&b| {
+        let (mut d, root) = (T::ONE, b.isqrt());
+        while d <= root {
+            if b % d == T::ZERO {
+                let q = b / d;
+                // `d` is a divisor in the row, or `q` is, with `d` the quotient
+                if d != b && set.contains(&d) {
+                    return Some(q);
                 }
-                if rem_b == 0 {
-                    sum += div_b;
-                    break 'line;
+                if q != b && set.contains(&q) {
+                    return Some(d);
                 }
             }
+            d = d + T::ONE;
         }
+        None
+    })
+}
+
+/// Part 1 without allocating, for the `no_std` build.  `None` if a number doesn't parse or the
+/// checksum doesn't fit.
+pub fn part1_str(input: &str) -> Option<Answer> {
+    input.lines().try_fold(0 as Answer, |sum, line| {
+        let (min, max) =
+            line.split_whitespace()
+                .try_fold((Answer::MAX, Answer::MIN), |(min, max), num| {
+                    let num = num.parse::<Answer>().ok()?;
+                    Some((min.min(num), max.max(num)))
+                })?;
+        sum.checked_add(max.saturating_sub(min))
+    })
+}
+
+/// Part 2 without allocating, for the `no_std` build.  Walks each line's numbers twice instead
+/// of collecting them.  `None` if a number doesn't parse or the sum doesn't fit.
+pub fn part2_str(input: &str) -> Option<Answer> {
+    fn nums(line: &str) -> impl Iterator<Item = Option<Answer>> + '_ {
+        line.split_whitespace().map(|num| num.parse().ok())
     }
 
-    sum
+    input.lines().try_fold(0 as Answer, |sum, line| {
+        // check every number first, so the pairs below only see good ones
+        nums(line).try_for_each(|num| num.map(drop))?;
+        let pairs = || nums(line).flatten().enumerate();
+        let quotient = pairs().find_map(|(i, a)| {
+            pairs().find_map(|(j, b)| (i != j && b != 0 && a % b == 0).then_some(a / b))
+        });
+        sum.checked_add(quotient.unwrap_or(0))
+    })
 }
 
 #[cfg(test)]
@@ -102,4 +287,75 @@ mod tests {
     fn d2p2_input_test() {
         assert_eq!(part2(parse(INPUT.to_string())), 275);
     }
+
+    #[test]
+    fn d2_str_test() {
+        assert_eq!(part1_str(EXAMPLE), Some(18));
+        assert_eq!(part1_str(INPUT), Some(45351));
+        assert_eq!(part2_str(EXAMPLE2), Some(9));
+        assert_eq!(part2_str(INPUT), Some(275));
+
+        assert_eq!(part1_str("1 2\n3 x"), None);
+        assert_eq!(part1_str("0 4294967295\n0 4294967295"), None);
+        assert_eq!(part2_str("4 2 x"), None);
+        assert_eq!(part2_str("4294967295 1\n4294967295 1"), None);
+    }
+
+    #[test]
+    fn checksum_test() {
+        let sheet: Vec<Vec<u8>> = vec![vec![200, 100], vec![250, 10], vec![]];
+        assert_eq!(checksum(&sheet, min_max), Err(Error::Overflow { row: 2 }));
+        assert_eq!(checksum(&sheet, divisible), Ok(27));
+
+        let wide: Vec<Vec<u64>> = sheet
+            .iter()
+            .map(|row| row.iter().map(|&v| v.into()).collect())
+            .collect();
+        assert_eq!(checksum(&wide, min_max), Ok(340));
+
+        // any function of a row is a strategy
+        let largest = |row: &[u64]| row.iter().max().copied();
+        assert_eq!(checksum(&wide, largest), Ok(450));
+    }
+
+    #[test]
+    fn divisible_test() {
+        assert_eq!(divisible(&[7u32, 3, 21]), Some(7));
+        assert_eq!(divisible(&[0u32, 5, 9, 45]), Some(9));
+        assert_eq!(divisible(&[4u32, 9, 4]), Some(1));
+        assert_eq!(divisible(&[5u32, 7, 11]), None);
+        assert_eq!(divisible::<u32>(&[]), None);
+
+        // wide rows take the divisor search, narrow ones compare every pair
+        let mut row: Vec<u64> = (1000..3000).map(|n| 2 * n + 1).collect();
+        row.push(2003 * 3);
+        assert_eq!(divisible(&row), Some(3));
+        assert_eq!(divisible(&[250u8, 249, 125]), Some(2));
+    }
+
+    #[test]
+    fn parse_sheet_test() {
+        assert_eq!(Format::detect(INPUT), Format::Tsv);
+        assert_eq!(
+            parse_sheet::<u32>("1,2, 3\n\n4,5,6\n"),
+            Ok(vec![vec![1, 2, 3], vec![4, 5, 6]])
+        );
+        assert_eq!(
+            parse_sheet::<u32>("1\t2\n3\t4"),
+            Ok(vec![vec![1, 2], vec![3, 4]])
+        );
+        assert_eq!(
+            parse_sheet::<u8>("1,2\n3,300"),
+            Err(Error::NotANumber {
+                row: 2,
+                column: 2,
+                cell: "300".to_string()
+            })
+        );
+        assert_eq!(
+            parse_sheet::<u32>("1\t\t2").unwrap_err().to_string(),
+            "row 1, column 2: \"\" isn't a number"
+        );
+        assert_eq!(parse_sheet::<u32>("5 1  9"), Ok(vec![vec![5, 1, 9]]));
+    }
 }
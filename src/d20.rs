@@ -1,19 +1,326 @@
-//! A solution to day 20 year .
-//! https://adventofcode.com//day/20
+//! A solution to day 20 year 2017.
+//! https://adventofcode.com/2017/day/20
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::collections::HashMap;
+
+#[cfg(feature = "visualize")]
+pub use draw::{set_projection, Projection};
+
+type Model = Vec<Particle>;
+type Answer = usize;
+
+/// Ticks to simulate.  The input's last collision is a few dozen ticks in, and by this point the
+/// particles are sorted by acceleration, so the closest one stays closest.
+const TICKS: usize = 1000;
 
 pub fn parse(input: String) -> Model {
-    0
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Particle::parse)
+        .collect()
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(input, false)
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(input, true)
+}
+
+/// Simulate the swarm, returning the particle that stays closest to the origin, or with
+/// `collide` the number of particles left after collisions.
+fn solve(particles: Model, collide: bool) -> Answer {
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        return draw::run(particles, collide);
+    }
+
+    let mut swarm = Swarm::new(particles);
+    for _ in 0..TICKS {
+        swarm.tick();
+        if collide {
+            let colliding = swarm.collisions();
+            swarm.remove(&colliding);
+        }
+    }
+    swarm.answer(collide)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Particle {
+    p: [i64; 3],
+    v: [i64; 3],
+    a: [i64; 3],
+}
+
+impl Particle {
+    /// Parse `p=<x,y,z>, v=<x,y,z>, a=<x,y,z>`.
+    fn parse(line: &str) -> Self {
+        let nums: Vec<i64> = line
+            .split(|c: char| !(c.is_ascii_digit() || c == '-'))
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let vector = |i: usize| [nums[i], nums[i + 1], nums[i + 2]];
+
+        Self {
+            p: vector(0),
+            v: vector(3),
+            a: vector(6),
+        }
+    }
+
+    fn tick(&mut self) {
+        for i in 0..3 {
+            self.v[i] += self.a[i];
+            self.p[i] += self.v[i];
+        }
+    }
+
+    /// Manhattan distance from the origin.
+    fn distance(&self) -> i64 {
+        self.p.iter().map(|n| n.abs()).sum()
+    }
+}
+
+/// The particles still flying, with their numbers in the input.
+#[derive(Debug, Clone)]
+struct Swarm {
+    particles: Vec<Particle>,
+    ids: Vec<usize>,
+}
+
+impl Swarm {
+    fn new(particles: Vec<Particle>) -> Self {
+        Self {
+            ids: (0..particles.len()).collect(),
+            particles,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.particles.iter_mut().for_each(Particle::tick);
+    }
+
+    /// Which particles share their position with another.
+    fn collisions(&self) -> Vec<bool> {
+        let mut counts: HashMap<[i64; 3], usize> = HashMap::new();
+        for particle in &self.particles {
+            *counts.entry(particle.p).or_default() += 1;
+        }
+        self.particles.iter().map(|p| counts[&p.p] > 1).collect()
+    }
+
+    fn remove(&mut self, colliding: &[bool]) {
+        let mut keep = colliding.iter().map(|&c| !c);
+        self.ids.retain(|_| keep.next().unwrap());
+        let mut keep = colliding.iter().map(|&c| !c);
+        self.particles.retain(|_| keep.next().unwrap());
+    }
+
+    /// The index into the swarm of the particle closest to the origin, if any are left.
+    fn closest(&self) -> Option<usize> {
+        (0..self.particles.len()).min_by_key(|&i| self.particles[i].distance())
+    }
+
+    fn answer(&self, collide: bool) -> Answer {
+        match collide {
+            true => self.particles.len(),
+            false => self.ids[self.closest().unwrap()],
+        }
+    }
+}
+
+#[cfg(feature = "visualize")]
+mod draw {
+    use std::cell::Cell;
+    use std::f64::consts::TAU;
+
+    use super::{Answer, Particle, Swarm, TICKS};
+    use crate::viz::{self, Color, Frame, Style};
+
+    const WIDTH: usize = 72;
+    const HEIGHT: usize = 28;
+    /// Every tick is drawn while the swarm is busy colliding, then one in this many.
+    const BUSY: usize = 100;
+    const EVERY: usize = 25;
+    /// Ticks per full turn of the rotating view.
+    const TURN: usize = 400;
+
+    /// The plane the particles are projected onto.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Projection {
+        Xy,
+        Xz,
+        Yz,
+        /// Turning around the y axis as the particles move.
+        Rotate,
+    }
+
+    impl Projection {
+        pub fn parse(name: &str) -> Result<Self, String> {
+            match name {
+                "xy" => Ok(Self::Xy),
+                "xz" => Ok(Self::Xz),
+                "yz" => Ok(Self::Yz),
+                "rotate" => Ok(Self::Rotate),
+                _ => Err(format!(
+                    "unknown projection \"{name}\", expected xy, xz, yz or rotate"
+                )),
+            }
+        }
+
+        /// Where a particle lands on the screen's axes at `tick`.
+        fn project(self, [x, y, z]: [i64; 3], tick: usize) -> (f64, f64) {
+            let (x, y, z) = (x as f64, y as f64, z as f64);
+            match self {
+                Self::Xy => (x, y),
+                Self::Xz => (x, z),
+                Self::Yz => (y, z),
+                Self::Rotate => {
+                    let angle = TAU * tick as f64 / TURN as f64;
+                    (x * angle.cos() + z * angle.sin(), y)
+                }
+            }
+        }
+
+        fn describe(self) -> &'static str {
+            match self {
+                Self::Xy => "x-y plane",
+                Self::Xz => "x-z plane",
+                Self::Yz => "y-z plane",
+                Self::Rotate => "turning around the y axis",
+            }
+        }
+    }
+
+    thread_local! {
+        static PROJECTION: Cell<Projection> = const { Cell::new(Projection::Rotate) };
+    }
+
+    /// Pick how the particles are drawn.  The runner calls this with `--projection`.
+    pub fn set_projection(projection: Projection) {
+        PROJECTION.with(|p| p.set(projection));
+    }
+
+    /// Run the simulation like [`super::solve`], drawing the swarm as it goes.
+    pub fn run(particles: Vec<Particle>, collide: bool) -> Answer {
+        let projection = PROJECTION.with(Cell::get);
+        let mut swarm = Swarm::new(particles);
+        let total = swarm.particles.len();
+
+        for tick in 0..=TICKS {
+            if tick > 0 {
+                swarm.tick();
+            }
+            let colliding = match collide {
+                true => swarm.collisions(),
+                false => vec![false; swarm.particles.len()],
+            };
+            let hit = colliding.contains(&true);
+
+            if viz::active() && (tick < BUSY || tick % EVERY == 0 || hit || tick == TICKS) {
+                // the closest of the particles that survive this tick
+                let closest = (0..swarm.particles.len())
+                    .filter(|&i| !colliding[i])
+                    .min_by_key(|&i| swarm.particles[i].distance());
+
+                let mut frame = draw(&swarm, &colliding, closest, projection, tick);
+                match collide {
+                    true => frame.status(format!(
+                        "tick {tick}: {} of {total} particles left",
+                        swarm.particles.len()
+                    )),
+                    false => frame.status(format!("tick {tick}: {total} particles")),
+                }
+                if let Some(i) = closest {
+                    frame.status(format!(
+                        "closest to the origin: particle {}, {} away",
+                        swarm.ids[i],
+                        swarm.particles[i].distance()
+                    ));
+                }
+                frame.status(projection.describe());
+                viz::emit(frame);
+            }
+
+            // colliding particles are drawn once, flashing, then they're gone
+            swarm.remove(&colliding);
+        }
+
+        swarm.answer(collide)
+    }
+
+    /// The swarm projected onto the screen, zoomed to fit most of it.  Characters are about
+    /// twice as tall as they are wide, so a row spans twice the distance of a column.
+    fn draw(
+        swarm: &Swarm,
+        colliding: &[bool],
+        closest: Option<usize>,
+        projection: Projection,
+        tick: usize,
+    ) -> Frame {
+        let points: Vec<(f64, f64)> = swarm
+            .particles
+            .iter()
+            .map(|particle| projection.project(particle.p, tick))
+            .collect();
+
+        // fit the middle 90% of the particles, so a few stragglers don't shrink everyone else
+        let range = |values: Vec<f64>| {
+            let mut values = values;
+            values.sort_by(f64::total_cmp);
+            let n = values.len().saturating_sub(1);
+            (values[n / 20], values[n - n / 20])
+        };
+        let mut frame = Frame::new(WIDTH, HEIGHT);
+        if points.is_empty() {
+            return frame;
+        }
+        let (x0, x1) = range(points.iter().map(|p| p.0).collect());
+        let (y0, y1) = range(points.iter().map(|p| p.1).collect());
+        let scale = ((x1 - x0) / (WIDTH - 1) as f64)
+            .max((y1 - y0) / (2 * (HEIGHT - 1)) as f64)
+            .max(1.0);
+        let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+
+        let mut counts = vec![0; WIDTH * HEIGHT];
+        let cell = |(x, y): (f64, f64)| {
+            let col = ((x - cx) / scale + (WIDTH - 1) as f64 / 2.0).round();
+            let row = ((y - cy) / (2.0 * scale) + (HEIGHT - 1) as f64 / 2.0).round();
+            (col >= 0.0 && row >= 0.0 && col < WIDTH as f64 && row < HEIGHT as f64)
+                .then_some((col as usize, row as usize))
+        };
+
+        for (i, &point) in points.iter().enumerate() {
+            let Some((x, y)) = cell(point) else {
+                continue;
+            };
+            counts[y * WIDTH + x] += 1;
+            if !colliding[i] {
+                let (ch, color) = match counts[y * WIDTH + x] {
+                    1 => ('·', Color::Gray),
+                    2 | 3 => ('•', Color::White),
+                    _ => ('●', Color::White),
+                };
+                frame.set(x, y, ch, Style::fg(color));
+            }
+        }
+
+        for (i, &point) in points.iter().enumerate() {
+            if let (true, Some((x, y))) = (colliding[i], cell(point)) {
+                frame.set(x, y, '*', Style::fg(Color::Red).bold());
+            }
+        }
+        if let Some((x, y)) = closest.and_then(|i| cell(points[i])) {
+            frame.set(x, y, '@', Style::fg(Color::Green).bold());
+        }
+        frame
+    }
 }
 
 #[cfg(test)]
@@ -22,36 +329,43 @@ mod tests {
 
     const INPUT: &str = include_str!("../input/d20");
     const EXAMPLE: &str = include_str!("../examples/d20");
+    const EXAMPLE2: &str = include_str!("../examples/d20-2");
 
     #[test]
     fn d20p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 0);
     }
 
     #[test]
     fn d20p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 344);
     }
 
     #[test]
     fn d20p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE2.to_string())), 1);
     }
 
     #[test]
     fn d20p2_input_test() {
-        assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
-        );
+        assert_eq!(part2(parse(INPUT.to_string())), 404);
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn d20_draw_test() {
+        set_projection(Projection::Xy);
+        let (answer, frames) = crate::viz::capture(|| part2(parse(EXAMPLE2.to_string())));
+        assert_eq!(answer, 1);
+        assert!(Projection::parse("zx").is_err());
+
+        // three particles meet at tick 2, and flash before they're removed
+        let status = |i: usize| frames[i].status.join("\n");
+        assert!(status(2).starts_with("tick 2: 4 of 4 particles left"));
+        assert!(status(3).starts_with("tick 3: 1 of 4 particles left"));
+        let stars = |i: usize| frames[i].to_plain().matches('*').count();
+        assert_eq!(stars(2), 1);
+        assert_eq!(stars(3), 0);
+        assert_eq!(frames[3].to_plain().matches('@').count(), 1);
     }
 }
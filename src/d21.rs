@@ -1,19 +1,162 @@
-//! A solution to day 21 year .
-//! https://adventofcode.com//day/21
+//! A solution to day 21 year 2017.
+//! https://adventofcode.com/2017/day/21
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use crate::image::{self, Image, Rgb};
+
+type Model = Rules;
+type Answer = usize;
+
+const START: &str = ".#./..#/###";
+const OFF: Rgb = [20, 16, 40];
+const ON: Rgb = [250, 200, 60];
 
 pub fn parse(input: String) -> Model {
-    0
+    Rules::parse(&input)
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input, 5)
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input, 18)
+}
+
+/// The number of pixels on after `iterations` enhancements of the starting pattern.
+fn solve(rules: &Rules, iterations: usize) -> Answer {
+    let mut grid = Grid::parse(START);
+    for _ in 0..iterations {
+        grid = grid.enhance(rules);
+    }
+
+    image::dump(|| grid.draw());
+    grid.cells.iter().filter(|&&on| on).count()
+}
+
+/// A square pattern, row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Grid {
+    size: usize,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    fn parse(pattern: &str) -> Self {
+        let cells: Vec<bool> = pattern
+            .split('/')
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        Self {
+            size: pattern.split('/').count(),
+            cells,
+        }
+    }
+
+    /// The `size` square block at (x, y) as a bit pattern, first cell in the lowest bit.
+    fn block(&self, x: usize, y: usize, size: usize) -> u16 {
+        (0..size * size).fold(0, |bits, i| {
+            let on = self.cells[(y + i / size) * self.size + x + i % size];
+            bits | (on as u16) << i
+        })
+    }
+
+    /// Split into 2x2 or 3x3 blocks and replace each with the rule's output.
+    fn enhance(&self, rules: &Rules) -> Self {
+        let from = if self.size.is_multiple_of(2) { 2 } else { 3 };
+        let to = from + 1;
+        let blocks = self.size / from;
+
+        let mut out = Self {
+            size: blocks * to,
+            cells: vec![false; blocks * to * blocks * to],
+        };
+        for by in 0..blocks {
+            for bx in 0..blocks {
+                let pattern = rules.get(self.block(bx * from, by * from, from), from);
+                for i in 0..to * to {
+                    out.cells[(by * to + i / to) * out.size + bx * to + i % to] =
+                        pattern >> i & 1 == 1;
+                }
+            }
+        }
+        out
+    }
+
+    /// A quarter turn clockwise.
+    fn rotate(&self) -> Self {
+        let n = self.size;
+        Self {
+            size: n,
+            cells: (0..n * n)
+                .map(|i| self.cells[(n - 1 - i % n) * n + i / n])
+                .collect(),
+        }
+    }
+
+    /// Mirrored left to right.
+    fn flip(&self) -> Self {
+        let n = self.size;
+        Self {
+            size: n,
+            cells: (0..n * n)
+                .map(|i| self.cells[i / n * n + n - 1 - i % n])
+                .collect(),
+        }
+    }
+
+    fn draw(&self) -> Image {
+        // keep small grids big enough to see
+        let scale = (1024 / self.size).max(1);
+        Image::from_grid(self.size, self.size, scale, vec![OFF, ON], |x, y| {
+            self.cells[y * self.size + x] as u8
+        })
+    }
+}
+
+/// The enhancement rules, indexed by the bit pattern of every rotation and flip of their inputs.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    /// 2x2 inputs to 3x3 outputs.
+    two: Vec<Option<u16>>,
+    /// 3x3 inputs to 4x4 outputs.
+    three: Vec<Option<u16>>,
+}
+
+impl Rules {
+    fn parse(input: &str) -> Self {
+        let mut rules = Self {
+            two: vec![None; 1 << 4],
+            three: vec![None; 1 << 9],
+        };
+
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let (from, to) = line.trim().split_once(" => ").unwrap();
+            let mut grid = Grid::parse(from);
+            let to = Grid::parse(to).block(0, 0, grid.size + 1);
+
+            let table = match grid.size {
+                2 => &mut rules.two,
+                3 => &mut rules.three,
+                _ => panic!("rules must match 2x2 or 3x3 blocks: \"{line}\""),
+            };
+            for _ in 0..2 {
+                for _ in 0..4 {
+                    table[grid.block(0, 0, grid.size) as usize] = Some(to);
+                    grid = grid.rotate();
+                }
+                grid = grid.flip();
+            }
+        }
+
+        rules
+    }
+
+    fn get(&self, pattern: u16, size: usize) -> u16 {
+        let table = if size == 2 { &self.two } else { &self.three };
+        table[pattern as usize].expect("no rule matches a block")
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +168,32 @@ mod tests {
 
     #[test]
     fn d21p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(solve(&parse(EXAMPLE.to_string()), 2), 12);
     }
 
     #[test]
     fn d21p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 205);
     }
 
     #[test]
-    fn d21p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+    fn d21p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 3389823);
     }
 
     #[test]
-    fn d21p2_input_test() {
+    fn symmetry_test() {
+        let grid = Grid::parse(START);
+        assert_eq!(grid.rotate(), Grid::parse("#../#.#/##."));
+        assert_eq!(grid.flip(), Grid::parse(".#./#../###"));
+        assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+
+        // the example's 3x3 rule matches the start flipped upside down
+        let rules = parse(EXAMPLE.to_string());
+        let upside_down = Grid::parse("###/..#/.#.");
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            rules.get(upside_down.block(0, 0, 3), 3),
+            Grid::parse("#..#/..../..../#..#").block(0, 0, 4)
         );
     }
 }
@@ -1,19 +1,148 @@
-//! A solution to day 22 year .
-//! https://adventofcode.com//day/22
+//! A solution to day 22 year 2017.
+//! https://adventofcode.com/2017/day/22
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use crate::image::{self, Image, Rgb};
+
+type Model = Cluster;
+type Answer = usize;
+
+/// Colours of clean, weakened, infected and flagged nodes, then the carrier.
+const PALETTE: [Rgb; 5] = [
+    [16, 16, 24],
+    [230, 180, 40],
+    [200, 40, 40],
+    [60, 120, 230],
+    [255, 255, 255],
+];
 
 pub fn parse(input: String) -> Model {
-    0
+    Cluster::parse(&input)
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(input, 10_000, false)
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(input, 10_000_000, true)
+}
+
+/// The number of bursts that infect a node.
+fn solve(mut cluster: Cluster, bursts: usize, evolved: bool) -> Answer {
+    let infections = (0..bursts).filter(|_| cluster.burst(evolved)).count();
+    image::dump(|| cluster.draw());
+    infections
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Clean,
+    Weakened,
+    Infected,
+    Flagged,
+}
+
+/// The grid of nodes, stored densely and grown whenever the carrier walks off its edge.
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    size: usize,
+    nodes: Vec<Node>,
+    /// The carrier's position in the grid, and the direction it's facing.
+    x: usize,
+    y: usize,
+    dx: isize,
+    dy: isize,
+}
+
+impl Cluster {
+    fn parse(input: &str) -> Self {
+        let lines: Vec<&str> = input.lines().filter(|line| !line.is_empty()).collect();
+        let size = lines.len();
+        let nodes = lines
+            .iter()
+            .flat_map(|line| line.trim().chars())
+            .map(|c| {
+                if c == '#' {
+                    Node::Infected
+                } else {
+                    Node::Clean
+                }
+            })
+            .collect();
+
+        Self {
+            size,
+            nodes,
+            x: size / 2,
+            y: size / 2,
+            dx: 0,
+            dy: -1,
+        }
+    }
+
+    /// Work on the current node and move on.  Returns whether the node was infected.
+    fn burst(&mut self, evolved: bool) -> bool {
+        let node = &mut self.nodes[self.y * self.size + self.x];
+        let (dx, dy) = (self.dx, self.dy);
+        (self.dx, self.dy) = match *node {
+            Node::Clean => (dy, -dx),
+            Node::Weakened => (dx, dy),
+            Node::Infected => (-dy, dx),
+            Node::Flagged => (-dx, -dy),
+        };
+
+        *node = match (*node, evolved) {
+            (Node::Clean, false) => Node::Infected,
+            (Node::Infected, false) => Node::Clean,
+            (Node::Clean, true) => Node::Weakened,
+            (Node::Weakened, true) => Node::Infected,
+            (Node::Infected, true) => Node::Flagged,
+            (Node::Flagged, true) => Node::Clean,
+            (Node::Weakened | Node::Flagged, false) => unreachable!(),
+        };
+        let infected = *node == Node::Infected;
+
+        let (x, y) = (self.x as isize + self.dx, self.y as isize + self.dy);
+        if x < 0 || y < 0 || x as usize >= self.size || y as usize >= self.size {
+            self.grow();
+        }
+        self.x = self.x.wrapping_add_signed(self.dx);
+        self.y = self.y.wrapping_add_signed(self.dy);
+
+        infected
+    }
+
+    /// Pad the grid with clean nodes on every side.
+    fn grow(&mut self) {
+        let pad = self.size / 2 + 1;
+        let size = self.size + 2 * pad;
+
+        let mut nodes = vec![Node::Clean; size * size];
+        for (y, row) in self.nodes.chunks(self.size).enumerate() {
+            nodes[(y + pad) * size + pad..][..self.size].copy_from_slice(row);
+        }
+
+        self.nodes = nodes;
+        self.size = size;
+        self.x += pad;
+        self.y += pad;
+    }
+
+    fn draw(&self) -> Image {
+        let scale = (1024 / self.size).max(1);
+        Image::from_grid(
+            self.size,
+            self.size,
+            scale,
+            PALETTE.to_vec(),
+            |x, y| match (x, y) == (self.x, self.y) {
+                true => 4,
+                false => self.nodes[y * self.size + x] as u8,
+            },
+        )
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +154,47 @@ mod tests {
 
     #[test]
     fn d22p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(solve(parse(EXAMPLE.to_string()), 70, false), 41);
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 5587);
     }
 
     #[test]
     fn d22p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 5176);
     }
 
     #[test]
     fn d22p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(solve(parse(EXAMPLE.to_string()), 100, true), 26);
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 2511944);
     }
 
     #[test]
     fn d22p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 2512017);
+    }
+
+    #[test]
+    fn grow_test() {
+        let mut cluster = parse(EXAMPLE.to_string());
+        cluster.grow();
+        assert_eq!(cluster.size, 7);
+        assert_eq!((cluster.x, cluster.y), (3, 3));
+        assert_eq!(cluster.nodes[2 * 7 + 4], Node::Infected);
+        assert_eq!(cluster.nodes[3 * 7 + 2], Node::Infected);
+        assert_eq!(
+            cluster
+                .nodes
+                .iter()
+                .filter(|&&n| n == Node::Infected)
+                .count(),
+            2
+        );
+
+        let image = cluster.draw();
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            image.get(3 * image.width / 7, 3 * image.height / 7),
+            Some(4)
         );
     }
 }
@@ -1,19 +1,61 @@
-//! A solution to day 23 year .
-//! https://adventofcode.com//day/23
+//! A solution to day 23 year 2017.
+//! https://adventofcode.com/2017/day/23
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use crate::vm::{Asm, Machine, Operand, Registers};
+
+type Model = (Vec<Asm>, Registers);
+type Answer = u64;
 
 pub fn parse(input: String) -> Model {
-    0
+    let mut regs = Registers::new();
+    let program = Asm::parse_program(&input, &mut regs).unwrap();
+    (program, regs)
 }
 
-pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+/// The number of `mul` instructions executed in debug mode.
+pub fn part1((program, regs): Model) -> Answer {
+    let mut vm = Machine::new(program, regs);
+
+    #[cfg(feature = "visualize")]
+    crate::trace::Tracer::new().run(&mut vm, &mut ());
+    #[cfg(not(feature = "visualize"))]
+    vm.run(&mut ());
+
+    vm.count("mul")
+}
+
+/// The final value of `h` with `a` set to 1.
+///
+/// Running the program as written would take far too long.  It counts the composite numbers
+/// from `b` to `c` in steps of 17, testing each one by trying every pair of factors.  So run
+/// just the setup, up to the start of the outer loop (the target of the final unconditional
+/// jump), to find `b` and `c`, and count the composites directly.
+pub fn part2((program, mut regs): Model) -> Answer {
+    let a = regs.intern("a");
+    regs.set(a, 1);
+
+    let (start, step) = match program[..] {
+        [.., Asm::Sub(_, Operand::Imm(step)), Asm::Jnz(Operand::Imm(1), Operand::Imm(back))] => {
+            (program.len() as i64 - 1 + back, -step)
+        }
+        _ => panic!("the program doesn't end with the outer loop's increment and jump"),
+    };
+
+    let mut vm = Machine::new(program, regs);
+    vm.run_until(&mut (), |vm| vm.pc == start);
+
+    let b = vm.registers.by_name("b").unwrap();
+    let c = vm.registers.by_name("c").unwrap();
+    (b..=c)
+        .step_by(step as usize)
+        .filter(|&n| !is_prime(n))
+        .count() as Answer
 }
 
-pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+fn is_prime(n: i64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
 }
 
 #[cfg(test)]
@@ -21,37 +63,20 @@ mod tests {
     use super::*;
 
     const INPUT: &str = include_str!("../input/d23");
-    const EXAMPLE: &str = include_str!("../examples/d23");
-
-    #[test]
-    fn d23p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
-    }
 
     #[test]
     fn d23p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 4225);
     }
 
     #[test]
-    fn d23p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+    fn d23p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 905);
     }
 
     #[test]
-    fn d23p2_input_test() {
-        assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
-        );
+    fn is_prime_test() {
+        let primes: Vec<i64> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
     }
 }
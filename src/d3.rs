@@ -1,38 +1,42 @@
 //! A solution to day 3 year .
 //! https://adventofcode.com//day/3
 
-type Model = usize;
-type Answer = i32;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::collections::HashMap;
+use std::iter::FusedIterator;
+
+type Model = u64;
+type Answer = u128;
 
 pub fn parse(input: String) -> Model {
-    input
-        .parse::<usize>()
-        .expect("couldn't parse usize from input")
+    let n = input
+        .trim()
+        .parse::<u64>()
+        .expect("couldn't parse u64 from input");
+    assert!(n > 0, "the spiral starts at square 1, there's no square 0");
+    n
 }
 
 pub fn part1(input: Model) -> Answer {
-    let mut mem = SpiralMem::new();
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        return draw::walk(Part::P1, input);
+    }
 
-    mem.nth(input - 1).unwrap()
+    distance(input).into()
 }
 
 pub fn part2(input: Model) -> Answer {
-    let mut mem = SpiralMemStressTest::new();
-
-    mem.nth(input - 1).unwrap()
-}
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        return draw::walk(Part::P2, input);
+    }
 
-struct SpiralMem {
-    /// The current X coordinate.
-    x: i32,
-    /// The current Y coordinate.
-    y: i32,
-    /// Half the (width or height) of the current square spiral arm, floored.
-    radius: i32,
-    /// The travel direction for the next iteration.
-    next_dir: Direction,
-    /// Part 1 algorithm or part 2?
-    part: Part,
+    StressTest::default()
+        .map(|(_, value)| value)
+        .find(|&value| value > input.into())
+        .expect("the stress test values outgrew a u128")
 }
 
 enum Part {
@@ -40,117 +44,368 @@ enum Part {
     P2,
 }
 
-impl SpiralMem {
+/// The ring that square `n` is on, counting square 1 as ring 0.  Ring `k` ends with square
+/// `(2k + 1)²` in its bottom right corner.
+pub fn ring(n: u64) -> u64 {
+    assert!(n > 0, "the spiral starts at square 1");
+    (n - 1).isqrt().div_ceil(2)
+}
+
+/// Where square `n` is, with square 1 at the origin, x to the right and y up.
+pub fn position(n: u64) -> (i64, i64) {
+    let k = ring(n);
+    if k == 0 {
+        return (0, 0);
+    }
+
+    // the outer rings end past u64::MAX, so work in i128
+    let (n, k) = (i128::from(n), i128::from(k));
+
+    // squares back from the ring's last, which is its bottom right corner, going round the ring
+    // left along the bottom, up the left, right along the top, then down the right
+    let back = (2 * k + 1).pow(2) - n;
+    let side = 2 * k;
+    let (x, y) = match back / side {
+        0 => (k - back, -k),
+        1 => (-k, -k + back - side),
+        2 => (-k + back - 2 * side, k),
+        _ => (k, k - back + 3 * side),
+    };
+
+    // no further out than the ring, which is at most 2³¹
+    let fits = "a square's coordinates fit in an i64";
+    (x.try_into().expect(fits), y.try_into().expect(fits))
+}
+
+/// The square at (x, y), the inverse of [`position`], or `None` if its number doesn't fit in a
+/// `u64`.
+pub fn index(x: i64, y: i64) -> Option<u64> {
+    let (x, y) = (i128::from(x), i128::from(y));
+    let k = x.abs().max(y.abs());
+    let side = 2 * k;
+    let back = if y == -k {
+        k - x
+    } else if x == -k {
+        side + y + k
+    } else if y == k {
+        2 * side + x + k
+    } else {
+        3 * side + k - y
+    };
+    u64::try_from((2 * k + 1).checked_pow(2)? - back).ok()
+}
+
+/// Steps from square `n` back to square 1.
+pub fn distance(n: u64) -> u64 {
+    let (x, y) = position(n);
+    x.unsigned_abs() + y.unsigned_abs()
+}
+
+/// The squares' positions in the order they're written, starting with square 1, stepping round
+/// the spiral one square at a time.
+#[derive(Debug, Clone, Default)]
+pub struct Spiral {
+    x: i64,
+    y: i64,
+    /// The ring of the next square.
+    ring: i64,
+    started: bool,
+}
+
+impl Spiral {
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Iterator for Spiral {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some((0, 0));
+        }
+
+        let k = self.ring;
+        if (self.x, self.y) == (k, -k) {
+            // past the bottom right corner, onto the next ring
+            self.x += 1;
+            self.ring += 1;
+        } else if self.x == k && self.y < k {
+            self.y += 1;
+        } else if self.y == k && self.x > -k {
+            self.x -= 1;
+        } else if self.x == -k && self.y > -k {
+            self.y -= 1;
+        } else {
+            self.x += 1;
+        }
+
+        Some((self.x, self.y))
+    }
+}
+
+/// Which neighbouring squares add up to a square's value in the stress test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The squares above, below, left and right.
+    Four,
+    /// Diagonals too, as in the puzzle.
+    Eight,
+}
+
+impl Neighbourhood {
+    fn offsets(self) -> &'static [(i64, i64)] {
+        const EIGHT: [(i64, i64); 8] = [
+            (1, 0),
+            (0, 1),
+            (-1, 0),
+            (0, -1),
+            (1, 1),
+            (-1, 1),
+            (-1, -1),
+            (1, -1),
+        ];
+        match self {
+            Self::Four => &EIGHT[..4],
+            Self::Eight => &EIGHT,
+        }
+    }
+}
+
+/// The stress test's squares and their values, in the order they're written: square 1 holds the
+/// seed, and every later square the sum of its neighbours written so far.
+///
+/// Values are kept in a sparse grid and added with overflow checks, and the sequence ends rather
+/// than wrapping once a value won't fit in a `u128`, which with the puzzle's rules is after 1561
+/// squares.
+#[derive(Debug, Clone)]
+pub struct StressTest {
+    written: HashMap<(i64, i64), u128>,
+    spiral: Spiral,
+    neighbourhood: Neighbourhood,
+    seed: u128,
+    /// Set once a value overflows, since the spiral has already moved past its square.
+    done: bool,
+}
+
+impl StressTest {
+    pub fn new(neighbourhood: Neighbourhood, seed: u128) -> Self {
         Self {
-            x: 0,
-            y: 0,
-            radius: 0,
-            next_dir: Direction::Right,
+            written: HashMap::new(),
+            spiral: Spiral::new(),
+            neighbourhood,
+            seed,
+            done: false,
         }
     }
 }
 
-impl Iterator for SpiralMem {
-    type Item = i32;
+impl Default for StressTest {
+    /// The puzzle's stress test: eight neighbours, starting from 1.
+    fn default() -> Self {
+        Self::new(Neighbourhood::Eight, 1)
+    }
+}
+
+impl Iterator for StressTest {
+    type Item = ((i64, i64), u128);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // apply the appropriate algorithm for each part
-        match self.part {
-            Part::P1 => {
-                let dist = self.x.abs() + self.y.abs();
+        if self.done {
+            return None;
+        }
 
-                // update position and direction
-                match self.next_dir {
-                    Direction::Left => {
-                        self.x -= 1;
+        let (x, y) = self.spiral.next()?;
+        let value = match self.written.is_empty() {
+            true => Some(self.seed),
+            false => self
+                .neighbourhood
+                .offsets()
+                .iter()
+                .filter_map(|(dx, dy)| self.written.get(&(x + dx, y + dy)))
+                .try_fold(0u128, |sum, &value| sum.checked_add(value)),
+        };
+        let Some(value) = value else {
+            self.done = true;
+            return None;
+        };
 
-                        if self.x == -self.radius {
-                            self.next_dir = Direction::Down;
-                        }
-                    }
-                    Direction::Right => {
-                        self.x += 1;
-
-                        // if we just passed the bottom right corner, start a new spiral arm
-                        if self.x > self.radius {
-                            self.radius += 1;
-                            self.next_dir = Direction::Up;
-                        }
-                    }
-                    Direction::Up => {
-                        self.y += 1;
+        self.written.insert((x, y), value);
+        Some(((x, y), value))
+    }
+}
 
-                        if self.y == self.radius {
-                            self.next_dir = Direction::Left;
-                        }
-                    }
-                    Direction::Down => {
-                        self.y -= 1;
-                        if self.y == -self.radius {
-                            self.next_dir = Direction::Right;
-                        }
+impl FusedIterator for StressTest {}
+
+/// The spiral being laid out, for `just viz 3`.
+#[cfg(feature = "visualize")]
+mod draw {
+    use super::{distance, Answer, Part, Spiral, StressTest};
+    use crate::viz::{self, Color, Frame, Style};
+
+    /// Squares across the view before the spiral is drawn scaled down.
+    const VIEW: i64 = 41;
+    /// Roughly how many frames to show the spiral growing in.
+    const FRAMES: usize = 600;
+
+    /// Walk the spiral, drawing the squares as they're written.  For part 1 that's the first
+    /// `input` squares, followed by the way back to square 1, and for part 2 it's until a value
+    /// larger than the input is written.
+    pub fn walk(part: Part, input: u64) -> Answer {
+        let mut spiral = Canvas {
+            squares: Vec::new(),
+            radius: 0,
+            path: Vec::new(),
+        };
+
+        match part {
+            Part::P1 => {
+                let n = input as usize;
+                for (i, (x, y)) in (1..=n).zip(Spiral::new()) {
+                    spiral.write(x, y, None);
+
+                    // one frame per square to start with, then fewer as the rings get longer
+                    let every = (spiral.radius as usize).max(n / FRAMES).max(1);
+                    if i.is_multiple_of(every) || i == n {
+                        spiral.draw(format!("square {i}"));
                     }
                 }
 
-                Some(dist)
+                spiral.path_home();
+                distance(input).into()
             }
             Part::P2 => {
-                let neighbor1 = 1 + (self.x.abs() - 1 + self.y.abs());
-                let neighbor2 = 1 + (self.x.abs() + self.y.abs() - 1);
-                let this = 1 + (self.x.abs() + self.y.abs());
+                // the values grow so quickly that every square gets a frame
+                for (i, ((x, y), value)) in (1..).zip(StressTest::default()) {
+                    spiral.write(x, y, Some(value));
+                    spiral.draw(format!("square {i} holds {value}"));
+                    if value > input.into() {
+                        return value;
+                    }
+                }
+                panic!("the stress test values outgrew a u128")
+            }
+        }
+    }
+
+    struct Canvas {
+        /// Squares in the order they were written, with their values for part 2.
+        squares: Vec<(i64, i64, Option<Answer>)>,
+        /// How far the spiral reaches from square 1.
+        radius: i64,
+        /// Squares on the way back to square 1, highlighted at the end of part 1.
+        path: Vec<(i64, i64)>,
+    }
 
-                let mut dist = this + neighbor1 + neighbor2;
+    impl Canvas {
+        fn write(&mut self, x: i64, y: i64, value: Option<Answer>) {
+            self.radius = self.radius.max(x.abs()).max(y.abs());
+            self.squares.push((x, y, value));
+        }
 
-                let at_corner = self.x.abs() == self.radius && self.y.abs() == self.radius;
-                if !at_corner {
-                    dist += 1 + (self.x.abs() - 1 + self.y.abs() - 1);
+        /// Walk back from the last square to square 1, left or right first, then up or down.
+        fn path_home(&mut self) {
+            let &(mut x, mut y, _) = self.squares.last().unwrap();
+            let distance = (x.abs() + y.abs()) as usize;
+            let every = (distance / 60).max(1);
+
+            self.path.push((x, y));
+            while (x, y) != (0, 0) {
+                if x != 0 {
+                    x -= x.signum();
+                } else {
+                    y -= y.signum();
                 }
+                self.path.push((x, y));
 
-                // update position and direction
-                match self.next_dir {
-                    Direction::Left => {
-                        self.x -= 1;
+                let steps = self.path.len() - 1;
+                if steps.is_multiple_of(every) || steps == distance {
+                    self.draw(format!("{steps} of {distance} steps back to square 1"));
+                }
+            }
+        }
 
-                        if self.x == -self.radius {
-                            self.next_dir = Direction::Down;
-                        }
-                    }
-                    Direction::Right => {
-                        self.x += 1;
-
-                        // if we just passed the bottom right corner, start a new spiral arm
-                        if self.x > self.radius {
-                            self.radius += 1;
-                            self.next_dir = Direction::Up;
-                        }
-                    }
-                    Direction::Up => {
-                        if self.y == self.radius {
-                            self.next_dir = Direction::Left;
-                        }
-                    }
-                    Direction::Down => {
-                        self.y -= 1;
-                        if self.y == -self.radius {
-                            self.next_dir = Direction::Right;
-                        }
-                    }
+        fn draw(&self, status: String) {
+            if !viz::active() {
+                return;
+            }
+
+            // squares per cell, growing with the spiral so it always fits the view
+            let side = 2 * self.radius + 1;
+            let scale = side.div_ceil(VIEW);
+            let cells = side.div_ceil(scale) as usize;
+            let cell = |x: i64, y: i64| {
+                let col = (x + self.radius) / scale;
+                let row = (self.radius - y) / scale;
+                row as usize * cells + col as usize
+            };
+
+            // values only fit when every square has its own cell
+            let width = match scale {
+                1 => self
+                    .squares
+                    .iter()
+                    .filter_map(|&(_, _, value)| value)
+                    .map(|value| value.to_string().len() + 1)
+                    .max()
+                    .unwrap_or(2),
+                _ => 2,
+            };
+
+            // the last square written to each cell decides how it's drawn
+            let mut latest = vec![None; cells * cells];
+            for (i, &(x, y, _)) in self.squares.iter().enumerate() {
+                latest[cell(x, y)] = Some(i);
+            }
+
+            let mut on_path = vec![false; cells * cells];
+            for &(x, y) in &self.path {
+                on_path[cell(x, y)] = true;
+            }
+
+            let mut frame = Frame::new(cells * width, cells);
+            for (c, square) in latest.iter().enumerate() {
+                let Some(i) = *square else {
+                    continue;
+                };
+                let (x, y, value) = self.squares[i];
+
+                let ring = x.abs().max(y.abs());
+                let mut style = Style::fg(if ring % 2 == 0 {
+                    Color::Blue
+                } else {
+                    Color::Cyan
+                });
+                if i + 1 == self.squares.len() {
+                    style = Style::fg(Color::Yellow).bold();
+                }
+                if on_path[c] {
+                    style = Style::fg(Color::Red).bold();
                 }
+                if (x, y) == (0, 0) && scale == 1 {
+                    style = Style::fg(Color::White).bold();
+                }
+
+                let text = match value {
+                    Some(value) if scale == 1 => format!("{value:>0$} ", width - 1),
+                    _ => "█".repeat(width),
+                };
+                frame.print((c % cells) * width, c / cells, &text, style);
+            }
 
-                Some(dist)
+            let &(x, y, _) = self.squares.last().unwrap();
+            let ring = x.abs().max(y.abs());
+            frame.status(format!("{status}, at ({x}, {y}) on ring {ring}"));
+            if scale > 1 {
+                frame.status(format!("{scale}x{scale} squares per cell"));
             }
+            viz::emit(frame);
         }
     }
 }
 
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,16 +423,100 @@ mod tests {
         assert_eq!(part1(parse(INPUT.to_string())), 326);
     }
 
+    #[cfg(feature = "visualize")]
     #[test]
-    fn d3p2_example_test() {
-        assert_eq!(part2(parse(EXAMPLE.to_string())),);
+    fn d3_draw_test() {
+        let (answer, frames) = crate::viz::capture(|| part1(12));
+        assert_eq!(answer, 3);
+
+        // a frame per square, every other square once on ring 2, then one per step back
+        assert_eq!(frames.len(), 9 + 2 + 3);
+        assert_eq!(
+            frames.last().unwrap().to_plain(),
+            "\n  ████████\n  ████████\n  ████████\n\n3 of 3 steps back to square 1, at (2, 1) on ring 2\n"
+        );
     }
 
     #[test]
-    fn d3p2_input_test() {
+    fn parse_test() {
+        assert_eq!(parse("1024\n".to_string()), 1024);
+        assert!(std::panic::catch_unwind(|| parse("0".to_string())).is_err());
+    }
+
+    #[test]
+    fn spiral_test() {
+        // the closed forms agree with walking the spiral
+        for ((x, y), n) in Spiral::new().zip(1..=20_000u64) {
+            assert_eq!(position(n), (x, y), "square {n}");
+            assert_eq!(index(x, y), Some(n));
+            assert_eq!(ring(n), x.unsigned_abs().max(y.unsigned_abs()));
+            assert_eq!(distance(n), x.unsigned_abs() + y.unsigned_abs());
+        }
+
+        let first: Vec<(i64, i64)> = Spiral::new().take(10).collect();
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            first,
+            [
+                (0, 0),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (2, -1)
+            ]
         );
+
+        // far beyond anything worth walking
+        for n in [1 << 60, u64::MAX - 1, u64::MAX] {
+            let (x, y) = position(n);
+            assert_eq!(index(x, y), Some(n));
+        }
+        assert_eq!(index(1 << 32, 0), None);
+        assert_eq!(index(i64::MIN, i64::MAX), None);
+        assert_eq!(ring(9), 1);
+        assert_eq!(ring(10), 2);
+    }
+
+    #[test]
+    fn stress_test_test() {
+        let values: Vec<u128> = StressTest::default()
+            .map(|(_, value)| value)
+            .take(23)
+            .collect();
+        assert_eq!(
+            values,
+            [
+                1, 1, 2, 4, 5, 10, 11, 23, 25, 26, 54, 57, 59, 122, 133, 142, 147, 304, 330, 351,
+                362, 747, 806
+            ]
+        );
+
+        // only squares sharing an edge, starting from 2
+        let four: Vec<u128> = StressTest::new(Neighbourhood::Four, 2)
+            .map(|(_, value)| value)
+            .take(10)
+            .collect();
+        assert_eq!(four, [2, 2, 2, 4, 4, 6, 6, 8, 10, 10]);
+
+        // the sequence stops before it overflows, and stays stopped
+        let mut stress = StressTest::default();
+        let last = stress.by_ref().last().unwrap();
+        assert!(last.1 > u128::MAX / 16);
+        assert_eq!(stress.next(), None);
+        assert_eq!(stress.next(), None);
+    }
+
+    #[test]
+    fn d3p2_example_test() {
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 1968);
+    }
+
+    #[test]
+    fn d3p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 363010);
     }
 }
@@ -1,19 +1,194 @@
-//! A solution to day 4 year .
-//! https://adventofcode.com//day/4
+//! A solution to day 4 year 2017.
+//! https://adventofcode.com/2017/day/4
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+type Model = Vec<String>;
+type Answer = usize;
 
 pub fn parse(input: String) -> Model {
-    0
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn part1(passphrases: Model) -> Answer {
+    Policy::new().no_duplicates().count_valid(&passphrases)
+}
+
+pub fn part2(passphrases: Model) -> Answer {
+    Policy::new().no_anagrams().count_valid(&passphrases)
+}
+
+/// Words that should be rejected, as written in the passphrase.  `None` accepts the passphrase.
+type Conflict = Option<Vec<String>>;
+
+type Check = Box<dyn Fn(&[&str]) -> Conflict>;
+
+/// One check a passphrase has to pass.
+enum Rule {
+    NoDuplicates,
+    NoAnagrams,
+    MinWords(usize),
+    Custom { name: String, check: Check },
+}
+
+impl Rule {
+    fn name(&self) -> String {
+        match self {
+            Self::NoDuplicates => "no duplicates".to_string(),
+            Self::NoAnagrams => "no anagrams".to_string(),
+            Self::MinWords(n) => format!("at least {n} words"),
+            Self::Custom { name, .. } => name.clone(),
+        }
+    }
+
+    fn check(&self, words: &[&str], fold_case: bool) -> Conflict {
+        let fold = |word: &str| match fold_case {
+            true => word.to_lowercase(),
+            false => word.to_string(),
+        };
+
+        match self {
+            Self::NoDuplicates => first_conflict(words, fold),
+            Self::NoAnagrams => first_conflict(words, |word| signature(&fold(word))),
+            Self::MinWords(n) => {
+                (words.len() < *n).then(|| words.iter().map(|w| w.to_string()).collect())
+            }
+            Self::Custom { check, .. } => check(words),
+        }
+    }
+}
+
+/// The first group of words that share a key, in the order they appear, if any do.
+fn first_conflict<K: Hash + Eq>(words: &[&str], key: impl Fn(&str) -> K) -> Conflict {
+    let keys: Vec<K> = words.iter().map(|word| key(word)).collect();
+    let mut seen = HashSet::new();
+    let repeated = keys.iter().find(|&k| !seen.insert(k))?;
+
+    let group = words.iter().zip(&keys).filter(|(_, k)| *k == repeated);
+    Some(group.map(|(word, _)| word.to_string()).collect())
 }
 
-pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+/// A word's letters in order, which two words share exactly when they're anagrams of each other.
+pub fn signature(word: &str) -> Vec<char> {
+    let mut letters: Vec<char> = word.chars().collect();
+    letters.sort_unstable();
+    letters
 }
 
-pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+/// Why a passphrase was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    /// The passphrase's line, counting from 1.
+    pub line: usize,
+    /// The name of the first rule it broke.
+    pub rule: String,
+    /// The words that broke it.
+    pub words: Vec<String>,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} ({})",
+            self.line,
+            self.words.join(" "),
+            self.rule
+        )
+    }
+}
+
+/// Rules a passphrase must follow, checked in the order they were added.  Build one up from
+/// [`Policy::new`], which accepts anything.
+#[derive(Default)]
+pub struct Policy {
+    rules: Vec<Rule>,
+    fold_case: bool,
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject passphrases with the same word twice.
+    pub fn no_duplicates(mut self) -> Self {
+        self.rules.push(Rule::NoDuplicates);
+        self
+    }
+
+    /// Reject passphrases with two words made of the same letters, which includes duplicates.
+    pub fn no_anagrams(mut self) -> Self {
+        self.rules.push(Rule::NoAnagrams);
+        self
+    }
+
+    /// Reject passphrases with fewer than `n` words.
+    pub fn min_words(mut self, n: usize) -> Self {
+        self.rules.push(Rule::MinWords(n));
+        self
+    }
+
+    /// Ignore case when comparing words, so "Apple" duplicates "apple".
+    pub fn fold_case(mut self) -> Self {
+        self.fold_case = true;
+        self
+    }
+
+    /// Reject passphrases where `check` finds some words, which are reported as breaking the rule
+    /// called `name`.  It's given the words as written, whether or not case is folded.
+    pub fn rule(mut self, name: &str, check: impl Fn(&[&str]) -> Conflict + 'static) -> Self {
+        self.rules.push(Rule::Custom {
+            name: name.to_string(),
+            check: Box::new(check),
+        });
+        self
+    }
+
+    /// Check a passphrase against each rule in turn, reporting the name of the first one it
+    /// breaks and the words that broke it.  [`Policy::rejections`] says which line they're on.
+    pub fn check(&self, passphrase: &str) -> Result<(), (String, Vec<String>)> {
+        let words: Vec<&str> = passphrase.split_whitespace().collect();
+        self.rules
+            .iter()
+            .try_for_each(|rule| match rule.check(&words, self.fold_case) {
+                Some(words) => Err((rule.name(), words)),
+                None => Ok(()),
+            })
+    }
+
+    /// Every rejected passphrase, with the line it's on.
+    pub fn rejections<'a>(
+        &'a self,
+        passphrases: impl IntoIterator<Item = &'a str> + 'a,
+    ) -> impl Iterator<Item = Rejection> + 'a {
+        passphrases
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, passphrase)| {
+                let (rule, words) = self.check(passphrase).err()?;
+                Some(Rejection {
+                    line: i + 1,
+                    rule,
+                    words,
+                })
+            })
+    }
+
+    pub fn count_valid(&self, passphrases: &[String]) -> usize {
+        passphrases
+            .iter()
+            .filter(|passphrase| self.check(passphrase).is_ok())
+            .count()
+    }
 }
 
 #[cfg(test)]
@@ -22,36 +197,82 @@ mod tests {
 
     const INPUT: &str = include_str!("../input/d4");
     const EXAMPLE: &str = include_str!("../examples/d4");
+    const EXAMPLE2: &str = include_str!("../examples/d4-2");
 
     #[test]
     fn d4p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 2);
     }
 
     #[test]
     fn d4p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 386);
     }
 
     #[test]
     fn d4p2_example_test() {
+        assert_eq!(part2(parse(EXAMPLE2.to_string())), 3);
+    }
+
+    #[test]
+    fn d4p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 208);
+    }
+
+    #[test]
+    fn signature_test() {
+        // far too long for a product of primes, and not all lowercase ASCII
         assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
+            signature("conversationalists"),
+            signature("conservationalists")
+        );
+        assert_ne!(
+            signature("conversationalist"),
+            signature("conversationalists")
         );
+        assert_eq!(signature("Straße"), signature("ßStrae"));
+        assert_eq!(signature("日本"), signature("本日"));
     }
 
     #[test]
-    fn d4p2_input_test() {
+    fn policy_test() {
+        let policy =
+            Policy::new()
+                .min_words(2)
+                .fold_case()
+                .no_anagrams()
+                .rule("no digits", |words| {
+                    let digits: Vec<String> = words
+                        .iter()
+                        .filter(|word| word.contains(|c: char| c.is_ascii_digit()))
+                        .map(|word| word.to_string())
+                        .collect();
+                    (!digits.is_empty()).then_some(digits)
+                });
+
+        let passphrases = "Listen to Silent night\nalone\nab2 cd ef3\nÉcole école\nfine words";
+        let rejections: Vec<String> = policy
+            .rejections(passphrases.lines())
+            .map(|rejection| rejection.to_string())
+            .collect();
+        assert_eq!(
+            rejections,
+            [
+                "line 1: Listen Silent (no anagrams)",
+                "line 2: alone (at least 2 words)",
+                "line 3: ab2 ef3 (no digits)",
+                "line 4: École école (no anagrams)",
+            ]
+        );
+
+        // without case folding "Listen" and "Silent" are different letters
+        assert!(Policy::new().no_anagrams().check("Listen Silent").is_ok());
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            Policy::new().no_duplicates().check("a b a c b"),
+            Err((
+                "no duplicates".to_string(),
+                vec!["a".to_string(), "a".to_string()]
+            ))
         );
     }
 }
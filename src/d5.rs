@@ -1,19 +1,295 @@
-//! A solution to day 5 year .
-//! https://adventofcode.com//day/5
+//! A solution to day 5 year 2017.
+//! https://adventofcode.com/2017/day/5
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+type Model = Vec<isize>;
+type Answer = usize;
 
 pub fn parse(input: String) -> Model {
-    0
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("\"{line}\" isn't a jump offset"))
+        })
+        .collect()
+}
+
+pub fn part1(mut offsets: Model) -> Answer {
+    run(&mut offsets, increment)
+}
+
+pub fn part2(mut offsets: Model) -> Answer {
+    run_strange(&mut offsets)
+}
+
+/// Part 1's rule: every offset goes up by one after it's jumped from.
+pub fn increment(offset: isize) -> isize {
+    offset + 1
+}
+
+/// Part 2's rule: offsets of three or more go down by one instead.
+pub fn strange(offset: isize) -> isize {
+    if offset >= 3 {
+        offset - 1
+    } else {
+        offset + 1
+    }
+}
+
+/// Follow the jumps from the first offset until one leads out of the maze, changing each offset
+/// with `rule` after jumping from it.  Returns the number of jumps, the last one included.
+pub fn run(offsets: &mut [isize], rule: impl Fn(isize) -> isize) -> usize {
+    run_with(offsets, rule, &mut ())
+}
+
+/// Something told about every jump [`run_with`] makes.
+pub trait Observer {
+    /// Jump number `step`, counting from 1, went from `pc` by `jump`, and left `offset` behind.
+    fn jump(&mut self, step: usize, pc: usize, jump: isize, offset: isize);
+}
+
+/// Watches nothing, so [`run`] compiles to the bare loop.
+impl Observer for () {
+    #[inline(always)]
+    fn jump(&mut self, _: usize, _: usize, _: isize, _: isize) {}
+}
+
+/// [`run`], telling `observer` about each jump.
+pub fn run_with(
+    offsets: &mut [isize],
+    rule: impl Fn(isize) -> isize,
+    observer: &mut impl Observer,
+) -> usize {
+    let mut pc = 0;
+    let mut steps = 0;
+
+    while let Some(offset) = offsets.get_mut(pc) {
+        let jump = *offset;
+        *offset = rule(jump);
+        steps += 1;
+        observer.jump(steps, pc, jump, *offset);
+
+        match pc.checked_add_signed(jump) {
+            Some(next) => pc = next,
+            None => break,
+        }
+    }
+
+    steps
 }
 
-pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+/// Cells packed into each block of a [`Settled`] prefix.
+const BLOCK: usize = 16;
+
+/// The offsets at the start of the maze that have settled into 2s and 3s.  Under the
+/// [`strange`] rule those only ever swap with each other, and only ever jump forwards, so a run
+/// through them is decided by the block it starts in, which can be looked up rather than stepped.
+struct Settled {
+    /// Each cell's offset less 2, a bit per cell.
+    blocks: Vec<u16>,
+    /// For each block's bits and each place it can be entered by a jump from the block before,
+    /// the bits afterwards, how far past the block's end it's left, and the jumps taken.
+    table: Vec<(u16, u8, u8)>,
 }
 
-pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+impl Settled {
+    fn new() -> Self {
+        let mut table = Vec::with_capacity(3 << BLOCK);
+        for bits in 0..=u16::MAX {
+            for entry in 0..3 {
+                let (mut bits, mut pc, mut steps) = (bits, entry, 0);
+                while pc < BLOCK {
+                    let three = bits >> pc & 1;
+                    bits ^= 1 << pc;
+                    pc += 2 + three as usize;
+                    steps += 1;
+                }
+                table.push((bits, (pc - BLOCK) as u8, steps));
+            }
+        }
+
+        Self {
+            blocks: Vec::new(),
+            table,
+        }
+    }
+
+    /// The number of cells packed so far.
+    fn len(&self) -> usize {
+        self.blocks.len() * BLOCK
+    }
+
+    /// Pack the next block of offsets, which must all be 2s and 3s.
+    fn push(&mut self, offsets: &[isize]) {
+        let bits = offsets
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &offset)| bits | ((offset - 2) as u16) << i);
+        self.blocks.push(bits);
+    }
+
+    /// Copy the packed offsets back out.
+    fn unpack(&self, offsets: &mut [isize]) {
+        for (i, offset) in offsets[..self.len()].iter_mut().enumerate() {
+            *offset = 2 + (self.blocks[i / BLOCK] >> (i % BLOCK) & 1) as isize;
+        }
+    }
+}
+
+/// [`run`] with the [`strange`] rule, for part 2's tens of millions of jumps.  Most of them are
+/// through the settled prefix, which is crossed a block at a time.
+///
+/// Jumps inside a block are never made one by one, so there's nothing to tell an [`Observer`]
+/// about; trace a run with [`run_with`] and [`strange`] instead.
+pub fn run_strange(offsets: &mut [isize]) -> usize {
+    let mut settled = Settled::new();
+    // how many offsets from the start are 2s and 3s, which only grows
+    let mut prefix = 0;
+    let mut pc = 0;
+    let mut steps = 0;
+
+    while pc < offsets.len() {
+        if pc < settled.len() {
+            let (block, place) = (pc / BLOCK, pc % BLOCK);
+            let bits = &mut settled.blocks[block];
+            if place < 3 {
+                let (after, past, jumps) = settled.table[*bits as usize * 3 + place];
+                *bits = after;
+                pc = (block + 1) * BLOCK + past as usize;
+                steps += jumps as usize;
+            } else {
+                // landed in the middle of a block from further on, so step to the next one
+                let three = *bits >> place & 1;
+                *bits ^= 1 << place;
+                pc += 2 + three as usize;
+                steps += 1;
+            }
+            continue;
+        }
+
+        let jump = offsets[pc];
+        offsets[pc] = strange(jump);
+        steps += 1;
+
+        while prefix < offsets.len() && matches!(offsets[prefix], 2 | 3) {
+            prefix += 1;
+        }
+        while settled.len() + BLOCK <= prefix {
+            let start = settled.len();
+            settled.push(&offsets[start..start + BLOCK]);
+        }
+
+        match pc.checked_add_signed(jump) {
+            Some(next) => pc = next,
+            None => break,
+        }
+    }
+
+    settled.unpack(offsets);
+    steps
+}
+
+/// What happened in each cell of the maze during a run, for working out where the time goes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// Jumps taken from each cell.
+    pub visits: Vec<usize>,
+    /// The longest jump backwards, as (step, from, offset).
+    pub longest_back: Option<(usize, usize, isize)>,
+    cells: Vec<Cell>,
+}
+
+/// Enough of a cell's history to tell when it settled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Cell {
+    /// The offset before the last jump from it.
+    before: Option<isize>,
+    /// The step that left the cell with the first offset of its current back and forth, or
+    /// `None` if it's been going back and forth since the start.
+    since: Option<usize>,
+    /// The step of the last jump from it.
+    last: usize,
+    /// Whether its offset has gone back to an earlier one since then.
+    repeated: bool,
+}
+
+impl Trace {
+    pub fn new(len: usize) -> Self {
+        Self {
+            visits: vec![0; len],
+            longest_back: None,
+            cells: vec![Cell::default(); len],
+        }
+    }
+
+    /// The step after which each cell only ever swapped between two offsets, like part 2's 2s
+    /// and 3s, or `None` if it never settled.  A cell that settled before any jumps is `Some(0)`.
+    pub fn settled(&self) -> Vec<Option<usize>> {
+        self.cells
+            .iter()
+            .map(|cell| cell.repeated.then_some(cell.since.unwrap_or(0)))
+            .collect()
+    }
+
+    /// A line per cell: its index, the number of jumps from it, and when it settled.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "index,visits,settled\n".to_string();
+        for (i, (visits, settled)) in self.visits.iter().zip(self.settled()).enumerate() {
+            let settled = settled.map_or(String::new(), |step| step.to_string());
+            csv += &format!("{i},{visits},{settled}\n");
+        }
+        csv
+    }
+
+    /// Visits to the cells in `rows` equal ranges, as bars up to `width` characters long.
+    pub fn histogram(&self, rows: usize, width: usize) -> String {
+        let size = self.visits.len().div_ceil(rows.max(1)).max(1);
+        let totals: Vec<usize> = self
+            .visits
+            .chunks(size)
+            .map(|chunk| chunk.iter().sum())
+            .collect();
+        let most = totals.iter().copied().max().unwrap_or(0).max(1);
+        let digits = self.visits.len().to_string().len();
+
+        let mut out = String::new();
+        for (row, &total) in totals.iter().enumerate() {
+            let start = row * size;
+            let end = (start + size).min(self.visits.len()) - 1;
+            let bar = "█".repeat((total * width).div_ceil(most));
+            out += &format!("{start:>digits$}-{end:<digits$} {bar} {total}\n");
+        }
+        out
+    }
+}
+
+impl Observer for Trace {
+    fn jump(&mut self, step: usize, pc: usize, jump: isize, offset: isize) {
+        self.visits[pc] += 1;
+
+        let cell = &mut self.cells[pc];
+        if cell.before == Some(offset) {
+            cell.repeated = true;
+        } else if cell.before.is_some() {
+            // the back and forth starts again from the offset the last jump left
+            cell.since = Some(cell.last);
+            cell.repeated = false;
+        }
+        cell.before = Some(jump);
+        cell.last = step;
+
+        if jump < 0
+            && self
+                .longest_back
+                .is_none_or(|(_, _, longest)| jump < longest)
+        {
+            self.longest_back = Some((step, pc, jump));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +301,66 @@ mod tests {
 
     #[test]
     fn d5p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 5);
     }
 
     #[test]
     fn d5p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 372139);
     }
 
     #[test]
     fn d5p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 10);
     }
 
     #[test]
     fn d5p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 29629538);
+    }
+
+    #[test]
+    fn run_strange_test() {
+        // the fast path takes the same jumps, and leaves the maze the same
+        let mut offsets = parse(INPUT.to_string());
+        let mut expected = offsets.clone();
+        let steps = run(&mut expected, strange);
+
+        assert_eq!(run_strange(&mut offsets), steps);
+        assert_eq!(offsets, expected);
+
+        let mut example = parse(EXAMPLE.to_string());
+        run_strange(&mut example);
+        assert_eq!(example, [2, 3, 2, 3, -1]);
+
+        // leaving by the front of the maze
+        assert_eq!(run(&mut [1, -2], increment), 2);
+        assert_eq!(run_strange(&mut [1, 1, -3]), 3);
+    }
+
+    #[test]
+    fn trace_test() {
+        let mut offsets = parse(EXAMPLE.to_string());
+        let mut trace = Trace::new(offsets.len());
+        assert_eq!(run_with(&mut offsets, strange, &mut trace), 10);
+
+        assert_eq!(trace.visits, [2, 2, 2, 2, 2]);
+        assert_eq!(trace.longest_back, Some((4, 4, -3)));
+        // cell 1 went 3, 2, 3 from the start, but cell 3 went 1, 2, 3
+        assert_eq!(trace.settled(), [None, Some(0), None, None, None]);
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            trace.to_csv(),
+            "index,visits,settled\n0,2,\n1,2,0\n2,2,\n3,2,\n4,2,\n"
         );
+        assert_eq!(trace.histogram(3, 4), "0-1 ████ 4\n2-3 ████ 4\n4-4 ██ 2\n");
+
+        // part 2 settles nearly every cell, the front of the maze long before the back
+        let mut offsets = parse(INPUT.to_string());
+        let mut trace = Trace::new(offsets.len());
+        run_with(&mut offsets, strange, &mut trace);
+        let settled = trace.settled();
+        assert!(settled.iter().flatten().count() > 1000);
+        assert!(settled[10] < settled[1000]);
+        assert_eq!(trace.visits.iter().sum::<usize>(), 29629538);
     }
 }
@@ -1,19 +1,134 @@
 //! A solution to day 6 year .
 //! https://adventofcode.com//day/6
 
-type Model = u8;
-type Answer = String;
+use core::ops::DerefMut;
 
+use crate::cycle;
+use crate::fixed::FixedVec;
+
+/// Maximum number of memory banks in the `no_std` build.  The `std` build takes any number.
+pub const BANKS: usize = 16;
+
+#[cfg(any(test, not(feature = "no_std")))]
+type Model = Vec<u64>;
+type Answer = usize;
+
+#[cfg(any(test, not(feature = "no_std")))]
 pub fn parse(input: String) -> Model {
-    0
+    input
+        .split_whitespace()
+        .map(|n| n.parse().expect("invalid block count"))
+        .collect()
 }
 
-pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn part1(banks: Model) -> Answer {
+    reallocate(banks).steps
 }
 
-pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+#[cfg(any(test, not(feature = "no_std")))]
+pub fn part2(banks: Model) -> Answer {
+    reallocate(banks).loop_len
+}
+
+/// Parse the banks without allocating, for the `no_std` build.  `None` if a count doesn't
+/// parse, there are more than [`BANKS`] banks, or there are more blocks in all than fit in one
+/// bank, since they could all end up there.
+pub fn parse_str(input: &str) -> Option<FixedVec<u8, BANKS>> {
+    let mut banks = FixedVec::new();
+
+    for n in input.split_whitespace() {
+        banks.push(n.parse().ok()?).ok()?;
+    }
+    banks
+        .iter()
+        .try_fold(0u8, |total, &blocks| total.checked_add(blocks))?;
+
+    Some(banks)
+}
+
+/// Part 1 without allocating, for the `no_std` build.  `None` if the banks don't parse.
+pub fn part1_str(input: &str) -> Option<Answer> {
+    Some(reallocate(parse_str(input)?).steps)
+}
+
+/// Part 2 without allocating, for the `no_std` build.  `None` if the banks don't parse.
+pub fn part2_str(input: &str) -> Option<Answer> {
+    Some(reallocate(parse_str(input)?).loop_len)
+}
+
+/// How reallocating the blocks went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reallocation<B> {
+    /// Redistributions until a configuration is seen for the second time.
+    pub steps: usize,
+    /// Redistributions between the two sightings.
+    pub loop_len: usize,
+    /// The configuration that was seen twice.
+    pub repeated: B,
+}
+
+/// Redistribute the blocks until a configuration repeats.  Works on a `Vec` of any length, or on
+/// a [`FixedVec`] without allocating.  The total number of blocks must fit in one bank.
+///
+/// Brent's algorithm only keeps two configurations around, so unlike a "seen" set it needs no
+/// capacity limit and no allocation, and the repeated configuration is found by running up to it
+/// again.
+pub fn reallocate<B, T>(banks: B) -> Reallocation<B>
+where
+    B: Clone + Eq + DerefMut<Target = [T]>,
+    T: Copy + Ord + Into<u64> + TryFrom<u64>,
+{
+    let step = |banks: &mut B| redistribute(banks);
+    let found = cycle::brent(banks.clone(), step);
+    let repeated = found.nth(found.start, banks, step);
+
+    Reallocation {
+        steps: found.first_repeat(),
+        loop_len: found.len,
+        repeated,
+    }
+}
+
+/// Empty the fullest bank, leftmost on a tie, and deal its blocks out one at a time to the banks
+/// after it, wrapping round.  Every bank gets the same number of whole rounds, so those are
+/// added in one go, then the rest go to the banks straight after.
+fn redistribute<T>(banks: &mut [T])
+where
+    T: Copy + Ord + Into<u64> + TryFrom<u64>,
+{
+    let Some((first, blocks)) = position_max_first(banks) else {
+        return;
+    };
+    let len = banks.len();
+    let (rounds, rest) = (blocks / len as u64, (blocks % len as u64) as usize);
+
+    banks[first] = to_bank(0);
+    for (i, bank) in banks.iter_mut().enumerate() {
+        // banks first + 1 to first + rest, wrapping, get one of the remainder
+        let after = (i + len - first - 1) % len;
+        let extra = u64::from(after < rest);
+        *bank = to_bank((*bank).into() + rounds + extra);
+    }
+}
+
+/// A bank holding `blocks`, which fits as long as the total number of blocks does.
+fn to_bank<T: TryFrom<u64>>(blocks: u64) -> T {
+    T::try_from(blocks).unwrap_or_else(|_| panic!("{blocks} blocks don't fit in a bank"))
+}
+
+fn position_max_first<T: Copy + Ord + Into<u64>>(banks: &[T]) -> Option<(usize, u64)> {
+    // custom max function, since the std max functions (iter().max(), iter().position_max(),
+    // etc) all break ties with the last occurrence, not the first
+    let mut max_pos = None;
+
+    for (i, &blocks) in banks.iter().enumerate() {
+        if max_pos.is_none_or(|(_, max)| blocks > max) {
+            max_pos = Some((i, blocks));
+        }
+    }
+
+    max_pos.map(|(i, blocks)| (i, blocks.into()))
 }
 
 #[cfg(test)]
@@ -25,33 +140,80 @@ mod tests {
 
     #[test]
     fn d6p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 5);
     }
 
     #[test]
     fn d6p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 4074);
     }
 
     #[test]
     fn d6p2_example_test() {
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 4);
+    }
+
+    #[test]
+    fn d6p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 2793);
+    }
+
+    #[test]
+    fn redistribute_test() {
+        let mut banks = parse("12 12 14 8 1 0 6 6 5 5 2 2 8 2 16 12".to_string());
+        redistribute(&mut banks);
+        assert_eq!(banks, [13, 13, 15, 9, 2, 1, 7, 7, 6, 6, 3, 3, 9, 3, 1, 13]);
+
+        // a trillion blocks are dealt out in one pass
+        let mut banks: Vec<u64> = vec![3, 1_000_000_000_001, 0, 7];
+        redistribute(&mut banks);
+        assert_eq!(
+            banks,
+            [
+                250_000_000_003,
+                250_000_000_000,
+                250_000_000_001,
+                250_000_000_007
+            ]
+        );
+
+        let mut fixed = parse_str("12 12 14 8 1 0 6 6 5 5 2 2 8 2 16 12").unwrap();
+        redistribute(&mut fixed);
         assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
+            &*fixed,
+            &[13, 13, 15, 9, 2, 1, 7, 7, 6, 6, 3, 3, 9, 3, 1, 13]
         );
     }
 
     #[test]
-    fn d6p2_input_test() {
+    fn reallocate_test() {
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            reallocate(parse(EXAMPLE.to_string())),
+            Reallocation {
+                steps: 5,
+                loop_len: 4,
+                repeated: vec![2, 4, 1, 2],
+            }
         );
+
+        // more banks than the no_std build allows
+        let banks: Vec<u64> = (0..40).map(|i| i * 7 % 13).collect();
+        let found = reallocate(banks.clone());
+        let mut again = banks;
+        for _ in 0..found.steps - found.loop_len {
+            redistribute(&mut again);
+        }
+        assert_eq!(again, found.repeated);
+        assert_eq!(part1_str(INPUT), Some(4074));
+    }
+
+    #[test]
+    fn d6_str_errors_test() {
+        assert_eq!(part2_str("0 2 7 0"), Some(4));
+        assert_eq!(part1_str("0 2 x 0"), None);
+        assert_eq!(part1_str("0 2 256 0"), None);
+        assert_eq!(part1_str("1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17"), None);
+        // each bank fits, but all the blocks together wouldn't
+        assert_eq!(part2_str("200 100"), None);
     }
 }
@@ -1,19 +1,449 @@
-//! A solution to day 7 year .
-//! https://adventofcode.com//day/7
+//! A solution to day 7 year 2017.
+//! https://adventofcode.com/2017/day/7
 
-type Model = u8;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+use crate::graph::Graph;
+
+type Model = Tower;
 type Answer = String;
 
 pub fn parse(input: String) -> Model {
-    0
+    Tower::parse(&input).unwrap_or_else(|err| panic!("{err}"))
 }
 
+/// The name of the bottom program.
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    input.name(input.root()).to_string()
 }
 
+/// The weight the one wrong program should have to balance the tower.
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    let imbalance = input.imbalance().unwrap_or_else(|err| panic!("{err}"));
+
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        draw::show(&input, &imbalance);
+    }
+
+    imbalance.corrected.to_string()
+}
+
+/// The programs, indexed in input order, each with the programs it holds up.
+#[derive(Debug, Clone)]
+pub struct Tower {
+    names: Vec<String>,
+    weights: Vec<u32>,
+    /// An edge from each program to each one it holds up.
+    graph: Graph<usize>,
+    /// The program each one stands on, or `None` for the bottom program.
+    below: Vec<Option<usize>>,
+    /// Each program's weight plus everything it holds up.
+    totals: Vec<u32>,
+    root: usize,
+}
+
+/// The program whose weight throws the tower off balance, and the weight it should have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Imbalance {
+    pub program: usize,
+    pub corrected: u32,
+}
+
+/// Why a list of programs isn't a tower that one weight change can balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A line that isn't `name (weight)` with an optional `-> name, name, ...`, counting from 1.
+    BadLine {
+        line: usize,
+        text: String,
+    },
+    Empty,
+    Duplicate(String),
+    /// A program is held up by one that isn't listed.
+    Unknown(String),
+    /// A program is held up by more than one other.
+    HeldTwice(String),
+    /// Programs that end up holding themselves up, each holding up the next.
+    Cycle(Vec<String>),
+    /// More than one program stands on nothing.
+    SeveralRoots(Vec<String>),
+    /// Every program already holds up equal weights.
+    Balanced,
+    /// The programs this one holds up differ in a way no single program can explain, such as
+    /// just two that don't match, or three different totals.
+    Ambiguous(String),
+    /// Programs that would still be unbalanced after fixing the wrong one, so more than one
+    /// weight is wrong.
+    SeveralImbalances(Vec<String>),
+    /// The wrong program would need to weigh nothing or less.
+    TooLight(String),
+    /// A program carries more weight than a `u32` holds, itself included.
+    TooHeavy(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadLine { line, text } => write!(f, "line {line} isn't a program: \"{text}\""),
+            Self::Empty => write!(f, "there are no programs"),
+            Self::Duplicate(name) => write!(f, "{name} is listed more than once"),
+            Self::Unknown(name) => write!(f, "{name} is held up but never listed"),
+            Self::HeldTwice(name) => write!(f, "{name} is held up by more than one program"),
+            Self::Cycle(names) => write!(f, "programs hold each other up: {}", names.join(" -> ")),
+            Self::SeveralRoots(names) => {
+                write!(
+                    f,
+                    "more than one program is at the bottom: {}",
+                    names.join(", ")
+                )
+            }
+            Self::Balanced => write!(f, "the tower is already balanced"),
+            Self::Ambiguous(name) => {
+                write!(
+                    f,
+                    "can't tell which program {name} holds up is the wrong weight"
+                )
+            }
+            Self::SeveralImbalances(names) => write!(
+                f,
+                "more than one weight is wrong, {} would still be unbalanced",
+                names.join(", ")
+            ),
+            Self::TooLight(name) => write!(f, "{name} would have to weigh nothing to balance"),
+            Self::TooHeavy(name) => write!(f, "{name} carries too much weight to add up"),
+        }
+    }
+}
+
+impl Tower {
+    /// Parse lines like `fwft (72) -> ktlj, cntj, xhth`, or `ktlj (57)` for programs holding
+    /// nothing up, checking that they make a single tower.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let lines = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                Self::parse_line(line).ok_or_else(|| Error::BadLine {
+                    line: i + 1,
+                    text: line.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if lines.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        let mut index = HashMap::new();
+        for (i, &(name, ..)) in lines.iter().enumerate() {
+            if index.insert(name, i).is_some() {
+                return Err(Error::Duplicate(name.to_string()));
+            }
+        }
+
+        // every program is a node, in input order, even one that holds and stands on nothing
+        let mut graph = Graph::directed();
+        for i in 0..lines.len() {
+            graph.add_node(i);
+        }
+        let mut below = vec![None; lines.len()];
+        for (i, (.., names)) in lines.iter().enumerate() {
+            for &name in names {
+                let p = *index
+                    .get(name)
+                    .ok_or_else(|| Error::Unknown(name.to_string()))?;
+                if below[p].replace(i).is_some() {
+                    return Err(Error::HeldTwice(name.to_string()));
+                }
+                graph.add_edge(i, p);
+            }
+        }
+
+        let names: Vec<String> = lines.iter().map(|&(name, ..)| name.to_string()).collect();
+        let Some(order) = graph.topological_sort() else {
+            let cycle = graph
+                .cycle()
+                .expect("a graph that can't be sorted has a cycle");
+            return Err(Error::Cycle(
+                cycle.iter().map(|&&p| names[p].clone()).collect(),
+            ));
+        };
+        let roots = graph.roots();
+        if roots.len() > 1 {
+            return Err(Error::SeveralRoots(
+                roots.iter().map(|&&p| names[p].clone()).collect(),
+            ));
+        }
+
+        // working down from the top, everything a program holds up is already summed
+        let weights: Vec<u32> = lines.iter().map(|&(_, weight, _)| weight).collect();
+        let mut totals = weights.clone();
+        for &&p in order.iter().rev() {
+            totals[p] = graph
+                .neighbors(&p)
+                .try_fold(totals[p], |total, &q| total.checked_add(totals[q]))
+                .ok_or_else(|| Error::TooHeavy(names[p].clone()))?;
+        }
+
+        let root = *roots[0];
+        Ok(Self {
+            names,
+            weights,
+            graph,
+            below,
+            totals,
+            root,
+        })
+    }
+
+    fn parse_line(line: &str) -> Option<(&str, u32, Vec<&str>)> {
+        let (program, above) = line.split_once("->").unwrap_or((line, ""));
+        let (name, weight) = program.trim().split_once(' ')?;
+        let weight = weight.trim().strip_prefix('(')?.strip_suffix(')')?;
+        let above = above
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        Some((name, weight.parse().ok()?, above))
+    }
+
+    /// The programs `program` holds up.
+    fn above(&self, program: usize) -> Vec<usize> {
+        self.graph.neighbors(&program).copied().collect()
+    }
+
+    /// The bottom program.
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    pub fn name(&self, program: usize) -> &str {
+        &self.names[program]
+    }
+
+    /// The program's weight plus everything it holds up.
+    pub fn total(&self, program: usize) -> u32 {
+        self.totals[program]
+    }
+
+    /// Whether everything `program` holds up weighs the same.
+    fn is_balanced(&self, program: usize) -> bool {
+        let mut totals = self.graph.neighbors(&program).map(|&p| self.totals[p]);
+        totals
+            .next()
+            .is_none_or(|first| totals.all(|total| total == first))
+    }
+
+    /// Follow the odd one out up from the bottom until the programs above it balance.  That
+    /// program is the one with the wrong weight, as long as it's the only thing unbalancing the
+    /// tower.
+    pub fn imbalance(&self) -> Result<Imbalance, Error> {
+        let unbalanced: Vec<usize> = (0..self.names.len())
+            .filter(|&p| !self.is_balanced(p))
+            .collect();
+        if unbalanced.is_empty() {
+            return Err(Error::Balanced);
+        }
+
+        let mut program = self.root;
+        let mut expected = None;
+        let mut path = Vec::new();
+        while !self.is_balanced(program) {
+            let above = self.above(program);
+            let totals: Vec<u32> = above.iter().map(|&p| self.totals[p]).collect();
+            let count = |t: u32| totals.iter().filter(|&&u| u == t).count();
+            let odd = totals.iter().position(|&t| count(t) == 1);
+            let usual = totals.iter().copied().find(|&t| count(t) > 1);
+
+            let (Some(odd), Some(usual)) = (odd, usual) else {
+                return Err(Error::Ambiguous(self.names[program].clone()));
+            };
+            if count(usual) + 1 != totals.len() {
+                return Err(Error::Ambiguous(self.names[program].clone()));
+            }
+
+            path.push(program);
+            expected = Some(usual);
+            program = above[odd];
+        }
+
+        let others: Vec<String> = unbalanced
+            .iter()
+            .filter(|p| !path.contains(p))
+            .map(|&p| self.names[p].clone())
+            .collect();
+        let Some(expected) = expected.filter(|_| others.is_empty()) else {
+            return Err(Error::SeveralImbalances(others));
+        };
+
+        // what the program carries is the same either way
+        let carried = self.totals[program] - self.weights[program];
+        expected
+            .checked_sub(carried)
+            .filter(|&corrected| corrected > 0)
+            .map(|corrected| Imbalance { program, corrected })
+            .ok_or_else(|| Error::TooLight(self.names[program].clone()))
+    }
+
+    /// The tower as an indented tree, bottom program first, with each program's weight and the
+    /// total weight it carries.  The wrong program is marked with the weight it should have.
+    pub fn render(&self, charset: Charset) -> String {
+        let imbalance = self.imbalance().ok();
+        self.lines(charset, imbalance.as_ref(), &|_| true)
+            .into_iter()
+            .map(|(_, line)| line + "\n")
+            .collect()
+    }
+
+    /// The tree's lines, each with the program it shows.  Programs that aren't expanded show how
+    /// many programs they hold up instead.
+    fn lines(
+        &self,
+        charset: Charset,
+        imbalance: Option<&Imbalance>,
+        expand: &dyn Fn(usize) -> bool,
+    ) -> Vec<(usize, String)> {
+        let [tee, last, pipe, blank] = charset.branches();
+        let mut lines = Vec::new();
+
+        // programs still to show, with their indent and branch, next one last, so a tall tower
+        // doesn't need a deep stack
+        let mut stack = vec![(self.root, String::new(), "")];
+        while let Some((program, indent, branch)) = stack.pop() {
+            let mut line = format!(
+                "{indent}{branch}{} ({})",
+                self.names[program], self.weights[program]
+            );
+            let above = self.above(program);
+            if !above.is_empty() {
+                let _ = write!(line, " total {}", self.totals[program]);
+            }
+            if let Some(imbalance) = imbalance.filter(|i| i.program == program) {
+                let _ = write!(
+                    line,
+                    "  {} should weigh {}",
+                    charset.mark(),
+                    imbalance.corrected
+                );
+            }
+            if !above.is_empty() && !expand(program) {
+                let _ = write!(line, " [{} above]", self.count_above(program));
+                lines.push((program, line));
+                continue;
+            }
+            lines.push((program, line));
+
+            // children line up under their parent's name
+            let indent = match branch {
+                "" => indent,
+                branch if branch == last => indent + blank,
+                _ => indent + pipe,
+            };
+            for (i, &p) in above.iter().enumerate().rev() {
+                let branch = if i + 1 == above.len() { last } else { tee };
+                stack.push((p, indent.clone(), branch));
+            }
+        }
+
+        lines
+    }
+
+    /// The number of programs `program` holds up, directly or not.
+    fn count_above(&self, program: usize) -> usize {
+        self.graph.dfs(&program).len() - 1
+    }
+
+    /// The tower in Graphviz DOT format, with edges pointing up the tower and the wrong program
+    /// in red.
+    pub fn to_dot(&self) -> String {
+        let imbalance = self.imbalance().ok();
+        self.graph.to_dot_with(
+            |&program| self.names[program].clone(),
+            |&program| {
+                let mut label = format!("{}\n{}", self.names[program], self.weights[program]);
+                if self.graph.neighbors(&program).next().is_some() {
+                    let _ = write!(label, " / {}", self.totals[program]);
+                }
+                match imbalance.filter(|i| i.program == program) {
+                    Some(imbalance) => {
+                        let _ = write!(label, "\nshould weigh {}", imbalance.corrected);
+                        let red = || "red".to_string();
+                        vec![("label", label), ("color", red()), ("fontcolor", red())]
+                    }
+                    None => vec![("label", label)],
+                }
+            },
+        )
+    }
+}
+
+/// Characters to draw the tree's branches with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Ascii,
+    Unicode,
+}
+
+impl Charset {
+    /// A branch, the last branch, the line continuing past a branch, and the space after the last.
+    fn branches(self) -> [&'static str; 4] {
+        match self {
+            Self::Ascii => ["|-- ", "`-- ", "|   ", "    "],
+            Self::Unicode => ["├── ", "└── ", "│   ", "    "],
+        }
+    }
+
+    fn mark(self) -> &'static str {
+        match self {
+            Self::Ascii => "<--",
+            Self::Unicode => "◀",
+        }
+    }
+}
+
+#[cfg(feature = "visualize")]
+mod draw {
+    use super::{Charset, Imbalance, Tower};
+    use crate::viz::{self, Color, Frame, Style};
+
+    /// Show the tower with only the way up to the wrong program opened out, since the whole
+    /// thing is over a thousand lines.  It's a single picture, so it waits for a key press.
+    pub fn show(tower: &Tower, imbalance: &Imbalance) {
+        // the programs from the wrong one down to the bottom
+        let path: Vec<usize> =
+            std::iter::successors(Some(imbalance.program), |&p| tower.below[p]).collect();
+
+        let lines = tower.lines(Charset::Unicode, Some(imbalance), &|p| path.contains(&p));
+        let width = lines
+            .iter()
+            .map(|(_, line)| line.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut frame = Frame::new(width, lines.len());
+        for (y, (program, line)) in lines.iter().enumerate() {
+            let style = if *program == imbalance.program {
+                Style::fg(Color::Red).bold()
+            } else if path.contains(program) {
+                Style::fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            frame.print(0, y, line, style);
+        }
+        frame.status(format!(
+            "{} is at the bottom, and {} should weigh {}",
+            tower.names[tower.root], tower.names[imbalance.program], imbalance.corrected
+        ));
+
+        viz::pause();
+        viz::emit(frame);
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +455,149 @@ mod tests {
 
     #[test]
     fn d7p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), "tknk");
     }
 
     #[test]
     fn d7p1_input_test() {
+        assert_eq!(part1(parse(INPUT.to_string())), "svugo");
+    }
+
+    #[test]
+    fn d7p2_example_test() {
+        assert_eq!(part2(parse(EXAMPLE.to_string())), "60");
+    }
+
+    #[test]
+    fn d7p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), "1152");
+    }
+
+    #[test]
+    fn render_test() {
+        let tower = parse(EXAMPLE.to_string());
         assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
+            tower.render(Charset::Unicode),
+            "\
+tknk (41) total 778
+├── ugml (68) total 251  ◀ should weigh 60
+│   ├── gyxo (61)
+│   ├── ebii (61)
+│   └── jptl (61)
+├── padx (45) total 243
+│   ├── pbga (66)
+│   ├── havc (66)
+│   └── qoyq (66)
+└── fwft (72) total 243
+    ├── ktlj (57)
+    ├── cntj (57)
+    └── xhth (57)
+"
         );
+        assert!(tower
+            .render(Charset::Ascii)
+            .contains("|-- ugml (68) total 251  <-- should weigh 60\n|   |-- gyxo (61)\n"));
     }
 
     #[test]
-    fn d7p2_example_test() {
+    fn dot_test() {
+        let dot = parse(EXAMPLE.to_string()).to_dot();
+        assert!(dot.starts_with("digraph {\n    \"pbga\" [label=\"pbga\\n66\"];\n"));
+        assert!(dot.contains(
+            r#"    "ugml" [label="ugml\n68 / 251\nshould weigh 60", color="red", fontcolor="red"];"#
+        ));
+        assert!(dot.contains("    \"tknk\" -> \"ugml\";\n"));
+        assert_eq!(dot.matches("->").count(), 12);
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |input: &str| Tower::parse(input).unwrap_err().to_string();
+
+        assert_eq!(error(""), "there are no programs");
+        assert_eq!(error("a (1)\nb 2"), "line 2 isn't a program: \"b 2\"");
+        assert_eq!(error("a (1) -> b"), "b is held up but never listed");
+        assert_eq!(error("a (1)\na (2)"), "a is listed more than once");
+        assert_eq!(
+            error("a (1) -> c\nb (1) -> c\nc (1)"),
+            "c is held up by more than one program"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1)\nc (1) -> d\nd (1) -> e\ne (1) -> c"),
+            "programs hold each other up: c -> d -> e"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1)\nc (1)"),
+            "more than one program is at the bottom: a, c"
+        );
+
+        // a leaf on its own is a tower, just one with nothing to balance
+        let tower = Tower::parse("a (5)").unwrap();
+        assert_eq!(tower.name(tower.root()), "a");
+        assert_eq!(tower.total(tower.root()), 5);
+        assert_eq!(tower.imbalance(), Err(Error::Balanced));
+
+        let imbalance = |input: &str| Tower::parse(input).unwrap().imbalance();
         assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
+            imbalance("a (1) -> b, c\nb (1)\nc (2)"),
+            Err(Error::Ambiguous("a".to_string()))
         );
+        assert_eq!(
+            imbalance("a (1) -> b, c, d\nb (1)\nc (2)\nd (3)"),
+            Err(Error::Ambiguous("a".to_string()))
+        );
+        // a balances, but only because both sides are wrong
+        assert_eq!(
+            imbalance(
+                "a (1) -> b, c\nb (1) -> d, e, f\nc (1) -> g, h, i\n\
+                 d (1)\ne (1)\nf (3)\ng (1)\nh (1)\ni (3)"
+            )
+            .unwrap_err()
+            .to_string(),
+            "more than one weight is wrong, b, c would still be unbalanced"
+        );
+        assert_eq!(
+            imbalance("a (1) -> b, c, d\nb (5)\nc (5)\nd (2) -> e\ne (9)"),
+            Err(Error::TooLight("d".to_string()))
+        );
+
+        assert_eq!(
+            error("a (1) -> b, c\nb (4294967295)\nc (1)"),
+            "a carries too much weight to add up"
+        );
+
+        // far taller than the stack could recurse
+        let tall: String = (0..200_000)
+            .map(|i| format!("p{i} (1) -> p{}\n", i + 1))
+            .chain(["p200000 (1)".to_string()])
+            .collect();
+        let tower = Tower::parse(&tall).unwrap();
+        assert_eq!(tower.total(tower.root()), 200_001);
+        assert_eq!(tower.imbalance(), Err(Error::Balanced));
+        assert_eq!(
+            tower.lines(Charset::Ascii, None, &|_| false),
+            [(0, "p0 (1) total 200001 [200000 above]".to_string())]
+        );
+        assert_eq!(tower.lines(Charset::Ascii, None, &|p| p < 3).len(), 4);
     }
 
+    #[cfg(feature = "visualize")]
     #[test]
-    fn d7p2_input_test() {
+    fn d7_draw_test() {
+        let (answer, frames) = crate::viz::capture(|| part2(parse(EXAMPLE.to_string())));
+        assert_eq!(answer, "60");
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            frames[0].to_plain(),
+            "\
+tknk (41) total 778
+├── ugml (68) total 251  ◀ should weigh 60
+│   ├── gyxo (61)
+│   ├── ebii (61)
+│   └── jptl (61)
+├── padx (45) total 243 [3 above]
+└── fwft (72) total 243 [3 above]
+tknk is at the bottom, and ugml should weigh 60
+"
         );
     }
 }
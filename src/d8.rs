@@ -1,19 +1,111 @@
-//! A solution to day 8 year .
-//! https://adventofcode.com//day/8
+//! A solution to day 8 year 2017.
+//! https://adventofcode.com/2017/day/8
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use crate::vm::{Cmp, Flow, Instruction, Io, Machine, Reg, Registers, State};
+
+type Model = Machine<Inc>;
+type Answer = i64;
 
 pub fn parse(input: String) -> Model {
-    0
+    let mut regs = Registers::new();
+    let program = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Inc::parse(line, &mut regs).unwrap())
+        .collect();
+
+    Machine::new(program, regs)
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(input).0
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(input).1
+}
+
+/// The largest register value once the program has finished, and the largest value held in any
+/// register along the way.
+fn solve(mut vm: Model) -> (Answer, Answer) {
+    #[cfg(feature = "visualize")]
+    let mut tracer = crate::trace::Tracer::new();
+    #[cfg(feature = "visualize")]
+    tracer.observe(&vm);
+
+    let mut highest = 0;
+    loop {
+        let state = vm.step(&mut ());
+        #[cfg(feature = "visualize")]
+        tracer.observe(&vm);
+
+        highest = highest.max(vm.registers.max().unwrap_or(0));
+        if state != State::Running {
+            break;
+        }
+    }
+
+    (vm.registers.max().unwrap_or(0), highest)
+}
+
+/// `<reg> inc|dec <amount> if <reg> <cmp> <value>`, with decrements stored as negative
+/// increments.  Like the [`crate::vm::Asm`] instructions, the arithmetic wraps on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inc {
+    reg: Reg,
+    amount: i64,
+    cond_reg: Reg,
+    cond: Cmp,
+    value: i64,
+}
+
+impl Inc {
+    fn parse(line: &str, regs: &mut Registers) -> Result<Self, String> {
+        let error = || format!("bad instruction \"{line}\"");
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let [reg, op, amount, "if", cond_reg, cond, value] = words[..] else {
+            return Err(error());
+        };
+
+        let amount: i64 = amount.parse().map_err(|_| error())?;
+        Ok(Self {
+            reg: regs.intern(reg),
+            amount: match op {
+                "inc" => amount,
+                "dec" => amount.wrapping_neg(),
+                _ => return Err(error()),
+            },
+            cond_reg: regs.intern(cond_reg),
+            cond: Cmp::parse(cond).ok_or_else(error)?,
+            value: value.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+impl Instruction for Inc {
+    fn opcode(&self) -> &'static str {
+        "inc"
+    }
+
+    fn exec(&self, regs: &mut Registers, _io: &mut dyn Io) -> Flow {
+        if self.cond.eval(regs.get(self.cond_reg), self.value) {
+            regs.set(self.reg, regs.get(self.reg).wrapping_add(self.amount));
+        }
+        Flow::Next
+    }
+
+    fn describe(&self, regs: &Registers) -> String {
+        format!(
+            "{} inc {} if {} {} {}",
+            regs.name(self.reg),
+            self.amount,
+            regs.name(self.cond_reg),
+            self.cond.as_str(),
+            self.value
+        )
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +117,60 @@ mod tests {
 
     #[test]
     fn d8p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 1);
     }
 
     #[test]
     fn d8p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 6343);
     }
 
     #[test]
     fn d8p2_example_test() {
-        assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
-        );
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 10);
     }
 
     #[test]
     fn d8p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 7184);
+    }
+
+    #[test]
+    fn parse_test() {
+        let vm = parse("c dec -10 if a >= 1".to_string());
+        assert_eq!(vm.program[0].describe(&vm.registers), "c inc 10 if a >= 1");
+
+        let mut regs = Registers::new();
+        assert!(Inc::parse("a inc 1 when b < 5", &mut regs).is_err());
+        assert!(Inc::parse("a mul 1 if b < 5", &mut regs).is_err());
+        assert!(Inc::parse("a inc 1 if b <> 5", &mut regs).is_err());
+    }
+
+    #[test]
+    fn overflow_test() {
+        let vm = parse("a inc 9223372036854775807 if b == 0\na inc 2 if b == 0".to_string());
+        // b still holds 0, which beats a once it wraps round
+        assert_eq!(solve(vm), (0, i64::MAX));
+
+        let vm = parse("a dec -9223372036854775808 if b == 0\na dec 1 if b == 0".to_string());
+        assert_eq!(solve(vm), (i64::MAX, i64::MAX));
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn d8_draw_test() {
+        let (answers, frames) = crate::viz::capture(|| solve(parse(EXAMPLE.to_string())));
+        assert_eq!(answers, (1, 10));
+        assert_eq!(frames.len(), 5);
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            frames[4].to_plain(),
+            "    0  b inc 5 if a > 1          1 █ b 0
+    1  a inc 1 if b < 5          1 █ a 1
+    2  c inc 10 if a >= 1        1 █ c -10
+    3  c inc -20 if c == 10      1 █
+step 4, pc 4, halted
+inc 4
+"
         );
     }
 }
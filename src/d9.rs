@@ -1,19 +1,287 @@
-//! A solution to day 9 year .
-//! https://adventofcode.com//day/9
+//! A solution to day 9 year 2017.
+//! https://adventofcode.com/2017/day/9
 
-type Model = u8;
-type Answer = String;
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::fmt;
+use std::str::Chars;
+
+type Model = String;
+type Answer = u64;
 
 pub fn parse(input: String) -> Model {
-    0
+    input.trim().to_string()
 }
 
 pub fn part1(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input).unwrap_or_else(|err| panic!("{err}")).0
 }
 
 pub fn part2(input: Model) -> Answer {
-    "incomplete".to_string()
+    solve(&input).unwrap_or_else(|err| panic!("{err}")).1
+}
+
+/// The total score of all groups, and the number of non-cancelled characters in garbage.
+fn solve(input: &str) -> Result<(Answer, Answer), Unbalanced> {
+    #[cfg(feature = "visualize")]
+    if crate::viz::active() {
+        return draw::run(input);
+    }
+
+    let mut sm = StreamMachine::new(input.chars());
+    let answers = sm.by_ref().last().unwrap_or((0, 0));
+    sm.finish(input)?;
+    Ok(answers)
+}
+
+/// A `}` that closes a group that was never opened, at this character, counting from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unbalanced(pub usize);
+
+impl fmt::Display for Unbalanced {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "character {} closes a group that was never opened",
+            self.0
+        )
+    }
+}
+
+#[derive(Copy, Clone)]
+enum State {
+    EnjoyingLife,
+    ObservingGarbage,
+    /// Stopped at a `}` with no group open.
+    Unbalanced,
+}
+
+struct StreamMachine<'chars> {
+    stream: Chars<'chars>,
+    state: State,
+    score: Answer,
+    depth: u32,
+    garbage: Answer,
+}
+
+impl<'chars> StreamMachine<'chars> {
+    fn new(stream: Chars<'chars>) -> Self {
+        StreamMachine {
+            stream,
+            state: State::EnjoyingLife,
+            score: 0,
+            depth: 0,
+            garbage: 0,
+        }
+    }
+
+    /// Check the machine didn't stop early at an unbalanced `}` in `input`, the stream it read.
+    fn finish(&self, input: &str) -> Result<(), Unbalanced> {
+        match self.state {
+            State::Unbalanced => {
+                let read = input.len() - self.stream.as_str().len();
+                Err(Unbalanced(input[..read].chars().count() - 1))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Iterator for StreamMachine<'_> {
+    type Item = (Answer, Answer);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let State::Unbalanced = self.state {
+            return None;
+        }
+
+        if let Some(ch) = self.stream.next() {
+            // No matter what state we're in, ! cancels the following character, so handle that
+            // before handling the current state.
+            if ch == '!' {
+                self.stream.next(); // eat the next char to "Cancel" it
+                return Some((self.score, self.garbage));
+            }
+
+            match self.state {
+                State::EnjoyingLife => match ch {
+                    '{' => {
+                        self.depth += 1;
+                        self.score += Answer::from(self.depth);
+                    }
+                    '<' => {
+                        self.state = State::ObservingGarbage;
+                    }
+                    '}' => match self.depth.checked_sub(1) {
+                        Some(depth) => self.depth = depth,
+                        None => self.state = State::Unbalanced,
+                    },
+                    _ => {}
+                },
+                State::ObservingGarbage => {
+                    if ch == '>' {
+                        self.state = State::EnjoyingLife;
+                    } else {
+                        self.garbage += 1;
+                    }
+                }
+                State::Unbalanced => unreachable!(),
+            }
+
+            Some((self.score, self.garbage))
+        } else {
+            None
+        }
+    }
+}
+
+/// The stream machine stepping through the input, for `just viz 9`.
+#[cfg(feature = "visualize")]
+mod draw {
+    use super::{Answer, State, StreamMachine, Unbalanced};
+    use crate::viz::{self, Color, Frame, Style};
+
+    /// Characters per line of the stream.
+    const WIDTH: usize = 64;
+    /// Lines of the stream shown around the cursor.
+    const LINES: usize = 16;
+    /// At most about this many frames, so long inputs don't take forever.
+    const FRAMES: usize = 1500;
+
+    /// Groups are coloured by how deeply they're nested.
+    const DEPTH_COLORS: [Color; 6] = [
+        Color::Blue,
+        Color::Cyan,
+        Color::Green,
+        Color::Yellow,
+        Color::Magenta,
+        Color::Red,
+    ];
+
+    /// What the machine made of a character.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        Unread,
+        /// Part of a group (braces and commas) at this depth.
+        Group(u32),
+        Garbage,
+        /// A `!` or the character it cancelled.
+        Cancelled,
+    }
+
+    impl Kind {
+        fn style(self) -> Style {
+            match self {
+                Self::Unread => Style::default(),
+                Self::Group(0) => Style::fg(Color::White),
+                Self::Group(depth) => {
+                    Style::fg(DEPTH_COLORS[(depth as usize - 1) % DEPTH_COLORS.len()]).bold()
+                }
+                Self::Garbage => Style::fg(Color::Gray),
+                Self::Cancelled => Style::fg(Color::Gray).strike(),
+            }
+        }
+    }
+
+    /// Run the machine over `input` a step at a time, drawing the stream after each step.
+    pub fn run(input: &str) -> Result<(Answer, Answer), Unbalanced> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut kinds = vec![Kind::Unread; chars.len()];
+        let every = (chars.len() / FRAMES).max(1);
+
+        let mut machine = StreamMachine::new(input.chars());
+        let mut answers = (0, 0);
+        let mut pos = 0;
+        let mut steps = 0;
+
+        draw(&machine, &chars, &kinds, pos);
+
+        loop {
+            let rest = machine.stream.as_str();
+            let (state, depth) = (machine.state, machine.depth);
+            let Some(step) = machine.next() else {
+                break;
+            };
+            answers = step;
+
+            let read = &rest[..rest.len() - machine.stream.as_str().len()];
+            let kind = match (state, chars[pos]) {
+                (_, '!') => Kind::Cancelled,
+                (State::ObservingGarbage, _) | (_, '<') => Kind::Garbage,
+                (State::EnjoyingLife, '{') => Kind::Group(depth + 1),
+                // closing braces and commas belong to the group they're in
+                (State::EnjoyingLife, _) => Kind::Group(depth),
+                (State::Unbalanced, _) => unreachable!(),
+            };
+            let len = read.chars().count();
+            kinds[pos..pos + len].fill(kind);
+            pos += len;
+
+            steps += 1;
+            let stopped = matches!(machine.state, State::Unbalanced);
+            if steps % every == 0 || pos == chars.len() || stopped {
+                draw(&machine, &chars, &kinds, pos);
+            }
+        }
+
+        machine.finish(input)?;
+        Ok(answers)
+    }
+
+    fn draw(machine: &StreamMachine, chars: &[char], kinds: &[Kind], pos: usize) {
+        if !viz::active() {
+            return;
+        }
+
+        // short streams get a smaller view
+        let lines = chars.len().div_ceil(WIDTH).max(1);
+        let shown = lines.min(LINES);
+        let mut frame = Frame::new(WIDTH, shown + 2);
+
+        // the nesting depth as a bar, a block per level
+        let label = format!("depth {:>3} ", machine.depth);
+        frame.print(0, 0, &label, Style::default());
+        for level in 1..=machine.depth {
+            let x = label.len() + level as usize - 1;
+            frame.set(x, 0, '█', Kind::Group(level).style());
+        }
+
+        // keep the cursor's line in the middle of the view, until the end of the stream
+        let first = (pos / WIDTH).saturating_sub(shown / 2).min(lines - shown);
+
+        for line in 0..shown {
+            let start = (first + line) * WIDTH;
+            for x in 0..WIDTH {
+                let i = start + x;
+                let Some(&ch) = chars.get(i) else {
+                    break;
+                };
+
+                let style = if i == pos {
+                    Style {
+                        fg: Some(Color::Black),
+                        bg: Some(Color::Yellow),
+                        ..Style::default()
+                    }
+                } else {
+                    kinds[i].style()
+                };
+                frame.set(x, line + 2, ch, style);
+            }
+        }
+
+        let state = match machine.state {
+            State::EnjoyingLife => "in a group",
+            State::ObservingGarbage => "in garbage",
+            State::Unbalanced => "stopped, the last } closes nothing",
+        };
+        frame.status(format!("{state}, at character {pos} of {}", chars.len()));
+        frame.status(format!(
+            "score {}  garbage {}",
+            machine.score, machine.garbage
+        ));
+        viz::emit(frame);
+    }
 }
 
 #[cfg(test)]
@@ -25,33 +293,83 @@ mod tests {
 
     #[test]
     fn d9p1_example_test() {
-        assert_eq!(
-            part1(parse(EXAMPLE.to_string())),
-            "put part 1 example answer here"
-        );
+        assert_eq!(part1(parse(EXAMPLE.to_string())), 3);
     }
 
     #[test]
     fn d9p1_input_test() {
-        assert_eq!(
-            part1(parse(INPUT.to_string())), 
-            "put part 1 final answer here"
-        );
+        assert_eq!(part1(parse(INPUT.to_string())), 9251);
     }
 
     #[test]
     fn d9p2_example_test() {
+        assert_eq!(part2(parse(EXAMPLE.to_string())), 17);
+    }
+
+    #[test]
+    fn d9p2_input_test() {
+        assert_eq!(part2(parse(INPUT.to_string())), 4322);
+    }
+
+    #[test]
+    fn streams_test() {
+        let solve = |input| solve(input).unwrap();
+        assert_eq!(solve("{}"), (1, 0));
+        assert_eq!(solve("{{{}}}"), (6, 0));
+        assert_eq!(solve("{{},{}}"), (5, 0));
+        assert_eq!(solve("{{{},{},{{}}}}"), (16, 0));
+        assert_eq!(solve("{<a>,<a>,<a>,<a>}"), (1, 4));
+        assert_eq!(solve("{{<ab>},{<ab>},{<ab>},{<ab>}}"), (9, 8));
+        assert_eq!(solve("{{<!!>},{<!!>},{<!!>},{<!!>}}"), (9, 0));
+        assert_eq!(solve("{{<a!>},{<a!>},{<a!>},{<ab>}}"), (3, 17));
+        assert_eq!(solve("<>"), (0, 0));
+        assert_eq!(solve("<random characters>"), (0, 17));
+        assert_eq!(solve("<<<<>"), (0, 3));
+        assert_eq!(solve("<{!>}>"), (0, 2));
+        assert_eq!(solve("<!!>"), (0, 0));
+        assert_eq!(solve("<!!!>>"), (0, 0));
+        assert_eq!(solve(r#"<{o"i!a,<{i<a>"#), (0, 10));
+
+        // past what a u16 score could hold
+        assert_eq!(solve(&("{".repeat(400) + &"}".repeat(400))), (80_200, 0));
+    }
+
+    #[test]
+    fn unbalanced_test() {
+        assert_eq!(solve("}"), Err(Unbalanced(0)));
+        assert_eq!(solve("{<}>}}{"), Err(Unbalanced(5)));
         assert_eq!(
-            part2(parse(EXAMPLE.to_string())),
-            "put part 2 example answer here"
+            Unbalanced(5).to_string(),
+            "character 5 closes a group that was never opened"
         );
     }
 
+    #[cfg(feature = "visualize")]
     #[test]
-    fn d9p2_input_test() {
+    fn d9_draw_test() {
+        let (answers, frames) = crate::viz::capture(|| solve("{<!>a>,{}}"));
+        assert_eq!(answers, Ok((3, 1)));
+
+        // the first frame, then one per step: the "!>" pair is a single step
+        assert_eq!(frames.len(), 1 + 9);
+
+        let middle = &frames[3];
+        assert_eq!(
+            middle.to_plain(),
+            "depth   1 █\n\n{<!>a>,{}}\nin garbage, at character 4 of 10\nscore 1  garbage 0\n"
+        );
+        assert!(middle.get(2, 2).unwrap().style.strike);
         assert_eq!(
-            part2(parse(INPUT.to_string())),
-            "put part 2 final answer here"
+            middle.get(4, 2).unwrap().style.bg,
+            Some(crate::viz::Color::Yellow)
         );
+
+        // an unbalanced brace stops the machine, and the last frame says so
+        let (answers, frames) = crate::viz::capture(|| solve("{}}{}"));
+        assert_eq!(answers, Err(Unbalanced(2)));
+        assert_eq!(frames.len(), 1 + 3);
+        assert!(frames[3]
+            .to_plain()
+            .contains("stopped, the last } closes nothing, at character 3 of 5"));
     }
 }
@@ -0,0 +1,143 @@
+//! Fixed-capacity collections that never allocate, for the days that can run in the `no_std`
+//! build (see [`crate::nostd`]).
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+
+/// A vector with room for at most `N` items, stored inline.
+///
+/// Items must be `Copy + Default` so the unused slots can be filled without any unsafe code.
+#[derive(Clone, Copy)]
+pub struct FixedVec<T, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T: Copy + Default, const N: usize> FixedVec<T, N> {
+    pub fn new() -> Self {
+        Self {
+            items: [T::default(); N],
+            len: 0,
+        }
+    }
+
+    /// Append an item, handing it back if the vector is full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(item);
+        }
+
+        self.items[self.len] = item;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        Some(self.items[self.len])
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for FixedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for FixedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.items[..self.len]
+    }
+}
+
+impl<T, const N: usize> DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items[..self.len]
+    }
+}
+
+// Only the used slots take part in comparisons and hashing.
+
+impl<T: PartialEq, const N: usize> PartialEq for FixedVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for FixedVec<T, N> {}
+
+impl<T: Hash, const N: usize> Hash for FixedVec<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FixedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> TryFrom<&[T]> for FixedVec<T, N> {
+    type Error = ();
+
+    fn try_from(value: &[T]) -> Result<Self, Self::Error> {
+        let mut vec = Self::new();
+        for &item in value {
+            vec.push(item).map_err(|_| ())?;
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_vec_test() {
+        let mut vec = FixedVec::<u8, 3>::new();
+
+        assert!(vec.is_empty());
+        assert_eq!(vec.push(1), Ok(()));
+        assert_eq!(vec.push(2), Ok(()));
+        assert_eq!(vec.push(3), Ok(()));
+        assert_eq!(vec.push(4), Err(4));
+        assert!(vec.is_full());
+        assert_eq!(&*vec, &[1, 2, 3]);
+
+        vec[0] = 7;
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(&*vec, &[7, 2]);
+    }
+
+    #[test]
+    fn fixed_vec_eq_test() {
+        let a = FixedVec::<u8, 4>::try_from(&[1, 2][..]).unwrap();
+        let mut b = FixedVec::<u8, 4>::try_from(&[1, 2, 9][..]).unwrap();
+
+        assert_ne!(a, b);
+        b.pop();
+        assert_eq!(a, b);
+        assert!(FixedVec::<u8, 1>::try_from(&[1, 2][..]).is_err());
+    }
+}
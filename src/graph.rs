@@ -0,0 +1,560 @@
+//! Small graph toolkit shared by the graph-shaped days (7's tower, 12's pipes, 24's bridges).
+//!
+//! Nodes are identified by any hashable key (`&str` program names, `u32` program ids, ...) and
+//! stored in an adjacency list.  Internally every key is mapped to a dense index so the traversal
+//! code can work with plain `Vec`s.
+
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+pub struct Graph<K> {
+    /// Whether edges only go one way.
+    directed: bool,
+    /// Key to dense index.
+    ids: HashMap<K, usize>,
+    /// Dense index to key.
+    keys: Vec<K>,
+    /// Outgoing edges of each node (all edges, for undirected graphs).
+    out: Vec<Vec<usize>>,
+    /// Number of incoming edges of each node.
+    in_degree: Vec<usize>,
+}
+
+impl<K: Clone + Eq + Hash> Graph<K> {
+    /// Create a graph whose edges go from one node to another.
+    pub fn directed() -> Self {
+        Self::new(true)
+    }
+
+    /// Create a graph whose edges connect nodes in both directions.
+    pub fn undirected() -> Self {
+        Self::new(false)
+    }
+
+    fn new(directed: bool) -> Self {
+        Self {
+            directed,
+            ids: HashMap::new(),
+            keys: Vec::new(),
+            out: Vec::new(),
+            in_degree: Vec::new(),
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// Number of nodes in the graph.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.ids.contains_key(key)
+    }
+
+    /// All node keys, in the order they were added.
+    pub fn nodes(&self) -> impl Iterator<Item = &K> {
+        self.keys.iter()
+    }
+
+    /// Add a node if it doesn't exist yet, returning its dense index.
+    pub fn add_node(&mut self, key: K) -> usize {
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+
+        let id = self.keys.len();
+        self.ids.insert(key.clone(), id);
+        self.keys.push(key);
+        self.out.push(Vec::new());
+        self.in_degree.push(0);
+        id
+    }
+
+    /// Add an edge, creating either node as needed.  Duplicate edges are ignored.
+    pub fn add_edge(&mut self, from: K, to: K) {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+
+        self.link(from, to);
+        if !self.directed && from != to {
+            self.link(to, from);
+        }
+    }
+
+    fn link(&mut self, from: usize, to: usize) {
+        if !self.out[from].contains(&to) {
+            self.out[from].push(to);
+            self.in_degree[to] += 1;
+        }
+    }
+
+    /// Nodes reachable from `key` over a single edge.
+    pub fn neighbors(&self, key: &K) -> impl Iterator<Item = &K> {
+        self.ids
+            .get(key)
+            .into_iter()
+            .flat_map(|&id| self.out[id].iter().map(|&n| &self.keys[n]))
+    }
+
+    /// Every node reachable from `start`, in breadth-first order.
+    pub fn bfs(&self, start: &K) -> Vec<&K> {
+        let Some(&start) = self.ids.get(start) else {
+            return Vec::new();
+        };
+
+        let mut seen = vec![false; self.len()];
+        let mut queue = VecDeque::from([start]);
+        let mut order = Vec::new();
+        seen[start] = true;
+
+        while let Some(id) = queue.pop_front() {
+            order.push(&self.keys[id]);
+            for &n in &self.out[id] {
+                if !seen[n] {
+                    seen[n] = true;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Every node reachable from `start`, in depth-first preorder.
+    pub fn dfs(&self, start: &K) -> Vec<&K> {
+        let Some(&start) = self.ids.get(start) else {
+            return Vec::new();
+        };
+
+        let mut seen = vec![false; self.len()];
+        let mut stack = vec![start];
+        let mut order = Vec::new();
+
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            order.push(&self.keys[id]);
+
+            // push in reverse so neighbors are visited in insertion order
+            stack.extend(self.out[id].iter().rev().filter(|&&n| !seen[n]));
+        }
+
+        order
+    }
+
+    /// Group the nodes into connected components, ignoring edge direction.  Components are
+    /// ordered by their first-added node.
+    pub fn components(&self) -> Vec<Vec<&K>> {
+        let mut sets = DisjointSet::new(self.len());
+        for (from, tos) in self.out.iter().enumerate() {
+            for &to in tos {
+                sets.union(from, to);
+            }
+        }
+
+        let mut component_of: HashMap<usize, usize> = HashMap::new();
+        let mut components: Vec<Vec<&K>> = Vec::new();
+        for (id, key) in self.keys.iter().enumerate() {
+            let root = sets.find(id);
+            let next = components.len();
+            let c = *component_of.entry(root).or_insert(next);
+            if c == next {
+                components.push(Vec::new());
+            }
+            components[c].push(key);
+        }
+
+        components
+    }
+
+    /// Nodes with no incoming edges (e.g. the bottom program of day 7's tower).  Edges of an
+    /// undirected graph go both ways, so it has no roots, and this is always empty.
+    pub fn roots(&self) -> Vec<&K> {
+        if !self.directed {
+            return Vec::new();
+        }
+
+        (0..self.len())
+            .filter(|&id| self.in_degree[id] == 0)
+            .map(|id| &self.keys[id])
+            .collect()
+    }
+
+    /// Nodes with no outgoing edges.  Like [`Graph::roots`], this is always empty for an
+    /// undirected graph.
+    pub fn leaves(&self) -> Vec<&K> {
+        if !self.directed {
+            return Vec::new();
+        }
+
+        (0..self.len())
+            .filter(|&id| self.out[id].is_empty())
+            .map(|id| &self.keys[id])
+            .collect()
+    }
+
+    /// Order the nodes so every edge points forward, or `None` if the graph has a cycle.  Only
+    /// meaningful for directed graphs.
+    pub fn topological_sort(&self) -> Option<Vec<&K>> {
+        let mut in_degree = self.in_degree.clone();
+        let mut queue: VecDeque<usize> = (0..self.len()).filter(|&id| in_degree[id] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(id) = queue.pop_front() {
+            order.push(&self.keys[id]);
+            for &n in &self.out[id] {
+                in_degree[n] -= 1;
+                if in_degree[n] == 0 {
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// A cycle of nodes, each with an edge to the next and the last back to the first, or `None`
+    /// if there isn't one.  Like [`Graph::topological_sort`], only meaningful for directed graphs.
+    pub fn cycle(&self) -> Option<Vec<&K>> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Mark {
+            New,
+            Open,
+            Done,
+        }
+
+        let mut marks = vec![Mark::New; self.len()];
+        for start in 0..self.len() {
+            if marks[start] != Mark::New {
+                continue;
+            }
+
+            // the path being explored, with how many of each node's edges have been followed
+            let mut path = vec![(start, 0)];
+            marks[start] = Mark::Open;
+            while let Some((id, next)) = path.last_mut() {
+                let id = *id;
+                let Some(&n) = self.out[id].get(*next) else {
+                    marks[id] = Mark::Done;
+                    path.pop();
+                    continue;
+                };
+                *next += 1;
+
+                match marks[n] {
+                    Mark::New => {
+                        marks[n] = Mark::Open;
+                        path.push((n, 0));
+                    }
+                    Mark::Open => {
+                        let from = path.iter().position(|&(p, _)| p == n).unwrap();
+                        return Some(path[from..].iter().map(|&(p, _)| &self.keys[p]).collect());
+                    }
+                    Mark::Done => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Render the graph in Graphviz DOT format, with `name` giving each node's name and `attrs`
+    /// any attributes to draw it with, such as `("label", ...)` or `("color", "red")`.
+    pub fn to_dot_with(
+        &self,
+        name: impl Fn(&K) -> String,
+        attrs: impl Fn(&K) -> Vec<(&'static str, String)>,
+    ) -> String {
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+
+        let mut dot = format!("{kind} {{\n");
+        for key in &self.keys {
+            let attrs: Vec<String> = attrs(key)
+                .into_iter()
+                .map(|(attr, value)| format!("{attr}={}", quote(value)))
+                .collect();
+            if attrs.is_empty() {
+                writeln!(dot, "    {};", quote(name(key))).unwrap();
+            } else {
+                writeln!(dot, "    {} [{}];", quote(name(key)), attrs.join(", ")).unwrap();
+            }
+        }
+        for (from, tos) in self.out.iter().enumerate() {
+            for &to in tos {
+                // undirected edges are stored both ways but should only be drawn once
+                if self.directed || from <= to {
+                    let from = quote(name(&self.keys[from]));
+                    let to = quote(name(&self.keys[to]));
+                    writeln!(dot, "    {from} {arrow} {to};").unwrap();
+                }
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+impl<K: Clone + Eq + Hash + Display> Graph<K> {
+    /// Render the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(K::to_string, |_| Vec::new())
+    }
+}
+
+/// A DOT string holding `key`, with quotes and backslashes escaped and newlines kept as line
+/// breaks.
+fn quote(key: impl Display) -> String {
+    let mut quoted = String::from('"');
+    for ch in key.to_string().chars() {
+        match ch {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Union-find over the integers `0..n`, with path compression and union by size.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    count: usize,
+}
+
+impl DisjointSet {
+    /// Create `n` singleton sets.
+    pub fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            count: n,
+        }
+    }
+
+    /// Find the representative of the set containing `x`.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // point everything on the path straight at the root
+        let mut x = x;
+        while self.parent[x] != root {
+            x = std::mem::replace(&mut self.parent[x], root);
+        }
+
+        root
+    }
+
+    /// Merge the sets containing `a` and `b`.  Returns false if they were already the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        let (big, small) = if self.size[a] >= self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+        self.count -= 1;
+
+        true
+    }
+
+    /// Size of the set containing `x`.
+    pub fn size_of(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// Number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Day 12's example pipes.
+    fn pipes() -> Graph<u32> {
+        let mut graph = Graph::undirected();
+        for (from, tos) in [
+            (0, vec![2]),
+            (1, vec![1]),
+            (2, vec![0, 3, 4]),
+            (3, vec![2, 4]),
+            (4, vec![2, 3, 6]),
+            (5, vec![6]),
+            (6, vec![4, 5]),
+        ] {
+            for to in tos {
+                graph.add_edge(from, to);
+            }
+        }
+        graph
+    }
+
+    /// Day 7's example tower, with edges pointing up from each program to those it holds.
+    fn tower() -> Graph<&'static str> {
+        let mut graph = Graph::directed();
+        for (from, tos) in [
+            ("fwft", ["ktlj", "cntj", "xhth"]),
+            ("padx", ["pbga", "havc", "qoyq"]),
+            ("tknk", ["ugml", "padx", "fwft"]),
+            ("ugml", ["gyxo", "ebii", "jptl"]),
+        ] {
+            for to in tos {
+                graph.add_edge(from, to);
+            }
+        }
+        graph
+    }
+
+    #[test]
+    fn traversal_test() {
+        let graph = pipes();
+
+        assert_eq!(graph.bfs(&0), vec![&0, &2, &3, &4, &6, &5]);
+        assert_eq!(graph.dfs(&0), vec![&0, &2, &3, &4, &6, &5]);
+        assert_eq!(graph.bfs(&1), vec![&1]);
+        assert!(graph.bfs(&99).is_empty());
+    }
+
+    #[test]
+    fn components_test() {
+        let graph = pipes();
+        let components = graph.components();
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].len(), 6);
+        assert_eq!(components[1], vec![&1]);
+    }
+
+    #[test]
+    fn roots_and_leaves_test() {
+        let graph = tower();
+
+        assert_eq!(graph.roots(), vec![&"tknk"]);
+        assert_eq!(graph.leaves().len(), 9);
+
+        // every pipe goes both ways
+        assert!(pipes().roots().is_empty());
+        assert!(pipes().leaves().is_empty());
+    }
+
+    #[test]
+    fn topological_sort_test() {
+        let mut graph = tower();
+        let order = graph.topological_sort().unwrap();
+        let pos = |k: &str| order.iter().position(|&&n| n == k).unwrap();
+
+        assert_eq!(order.len(), graph.len());
+        assert!(pos("tknk") < pos("padx"));
+        assert!(pos("padx") < pos("havc"));
+
+        graph.add_edge("havc", "tknk");
+        assert!(graph.topological_sort().is_none());
+    }
+
+    #[test]
+    fn cycle_test() {
+        let mut graph = tower();
+        assert_eq!(graph.cycle(), None);
+
+        graph.add_edge("havc", "tknk");
+        assert_eq!(graph.cycle(), Some(vec![&"padx", &"havc", &"tknk"]));
+
+        let mut graph = Graph::directed();
+        graph.add_edge(1, 1);
+        assert_eq!(graph.cycle(), Some(vec![&1]));
+    }
+
+    #[test]
+    fn dot_test() {
+        let mut graph = Graph::undirected();
+        graph.add_edge("a", "b");
+        graph.add_edge("b", "a");
+
+        assert_eq!(
+            graph.to_dot(),
+            "graph {\n    \"a\";\n    \"b\";\n    \"a\" -- \"b\";\n}\n"
+        );
+
+        let mut graph = Graph::directed();
+        graph.add_edge(1, 2);
+        assert!(graph.to_dot().contains("\"1\" -> \"2\";"));
+
+        let mut graph = Graph::directed();
+        graph.add_edge(r#"say "hi""#, r"C:\tmp");
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph {
+    "say \"hi\"";
+    "C:\\tmp";
+    "say \"hi\"" -> "C:\\tmp";
+}
+"#
+        );
+
+        let mut graph = Graph::directed();
+        graph.add_edge(0, 1);
+        let dot = graph.to_dot_with(
+            |&id| ["a", "b"][id].to_string(),
+            |&id| match id {
+                0 => vec![("label", "a\n1".to_string()), ("color", "red".to_string())],
+                _ => Vec::new(),
+            },
+        );
+        assert_eq!(
+            dot,
+            r#"digraph {
+    "a" [label="a\n1", color="red"];
+    "b";
+    "a" -> "b";
+}
+"#
+        );
+    }
+
+    #[test]
+    fn disjoint_set_test() {
+        let mut sets = DisjointSet::new(5);
+
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert_eq!(sets.count(), 3);
+        assert_eq!(sets.size_of(1), 2);
+        assert_eq!(sets.find(0), sets.find(1));
+        assert_ne!(sets.find(0), sets.find(3));
+    }
+}
@@ -0,0 +1,925 @@
+//! Writing images and animations without pulling in any image crates.
+//!
+//! Everything here works on [`Image`]s whose pixels are indices into a palette of at most 256
+//! colours, which covers the puzzle grids and rendered terminal frames we draw.  The encoders are
+//! small rather than clever: PNG uses a single fixed-Huffman deflate block with greedy LZ77
+//! matching, and GIF uses plain LZW.  Animations only store the rectangle that changed since the
+//! previous frame.
+//!
+//! Days whose grids are too big for a terminal draw their final state with [`Image::from_grid`]
+//! and hand it to [`dump`], which saves it when the runner was given `--dump-image`.
+
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
+
+pub type Rgb = [u8; 3];
+
+/// Up to 256 colours, handing out an index per distinct colour.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<Rgb>,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The index for `color`, adding it if there's room.  Once the palette is full, the closest
+    /// existing colour is used instead.
+    pub fn index(&mut self, color: Rgb) -> u8 {
+        if let Some(i) = self.colors.iter().position(|&c| c == color) {
+            return i as u8;
+        }
+
+        if self.colors.len() < 256 {
+            self.colors.push(color);
+            return (self.colors.len() - 1) as u8;
+        }
+
+        let distance = |c: &Rgb| -> u32 {
+            (0..3)
+                .map(|i| (c[i] as i32 - color[i] as i32).pow(2) as u32)
+                .sum()
+        };
+        (0..256).min_by_key(|&i| distance(&self.colors[i])).unwrap() as u8
+    }
+
+    pub fn colors(&self) -> &[Rgb] {
+        &self.colors
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
+
+/// An image whose pixels index into a palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub palette: Vec<Rgb>,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// A new image filled with the palette's first colour.
+    pub fn new(width: usize, height: usize, palette: Vec<Rgb>) -> Self {
+        assert!(
+            !palette.is_empty() && palette.len() <= 256,
+            "palette must have 1 to 256 colours"
+        );
+
+        Self {
+            width,
+            height,
+            palette,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// Set one pixel.  Anything outside the image is clipped.
+    pub fn set(&mut self, x: usize, y: usize, color: u8) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Fill a `w` by `h` rectangle with its top left corner at (x, y).
+    pub fn fill(&mut self, x: usize, y: usize, w: usize, h: usize, color: u8) {
+        for y in y..(y + h).min(self.height) {
+            for x in x..(x + w).min(self.width) {
+                self.pixels[y * self.width + x] = color;
+            }
+        }
+    }
+
+    /// Draw a `width` by `height` grid with each cell a `scale` pixel square, coloured by the
+    /// palette index `cell(x, y)` returns.
+    pub fn from_grid(
+        width: usize,
+        height: usize,
+        scale: usize,
+        palette: Vec<Rgb>,
+        cell: impl Fn(usize, usize) -> u8,
+    ) -> Self {
+        let mut image = Self::new(width * scale, height * scale, palette);
+        for y in 0..height {
+            for x in 0..width {
+                image.fill(x * scale, y * scale, scale, scale, cell(x, y));
+            }
+        }
+        image
+    }
+
+    /// Save as a PNG or PPM file, going by the extension.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let bytes = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => self.to_png(),
+            Some("ppm") => self.to_ppm(),
+            _ => {
+                return Err(io::Error::other(format!(
+                    "can't save an image to {}, use a .png or .ppm file",
+                    path.display()
+                )))
+            }
+        };
+        fs::write(path, bytes)
+    }
+
+    /// Encode as a binary PPM, which most image tools read and which is trivial to write.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(self.pixels.iter().flat_map(|&i| self.palette[i as usize]));
+        ppm
+    }
+
+    /// Encode as an indexed-colour PNG.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = png_header(self);
+        chunk(&mut png, b"IDAT", &self.png_data(&self.bounds()));
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect {
+            x: 0,
+            y: 0,
+            w: self.width,
+            h: self.height,
+        }
+    }
+
+    /// The pixels in `rect` as zlib compressed PNG scanlines.
+    fn png_data(&self, rect: &Rect) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((rect.w + 1) * rect.h);
+        for y in rect.y..rect.y + rect.h {
+            // filter type 0, none: the palette indices compress well as they are
+            raw.push(0);
+            raw.extend_from_slice(&self.pixels[y * self.width + rect.x..][..rect.w]);
+        }
+        zlib(&raw)
+    }
+
+    /// The pixels in `rect`, row by row.
+    fn crop(&self, rect: &Rect) -> Vec<u8> {
+        (rect.y..rect.y + rect.h)
+            .flat_map(|y| &self.pixels[y * self.width + rect.x..][..rect.w])
+            .copied()
+            .collect()
+    }
+}
+
+/// `n` colours spread evenly around the colour wheel, with the lightness varying a little too, so
+/// that regions coloured by index are easy to tell apart.
+pub fn distinct(n: usize) -> Vec<Rgb> {
+    (0..n)
+        .map(|i| {
+            let hue = i as f64 / n as f64 * 6.0;
+            let light = [1.0, 0.7, 0.85][i % 3];
+            // the brightest channel, the dimmest, and the one ramping between them
+            let rise = hue.fract();
+            let (r, g, b) = match hue as usize {
+                0 => (1.0, rise, 0.0),
+                1 => (1.0 - rise, 1.0, 0.0),
+                2 => (0.0, 1.0, rise),
+                3 => (0.0, 1.0 - rise, 1.0),
+                4 => (rise, 0.0, 1.0),
+                _ => (1.0, 0.0, 1.0 - rise),
+            };
+            [r, g, b].map(|c: f64| (40.0 + c * 215.0 * light) as u8)
+        })
+        .collect()
+}
+
+thread_local! {
+    /// Where to save the final state of days that can draw one, and how that went.
+    static DUMP: RefCell<(Option<PathBuf>, Option<io::Result<()>>)> = const { RefCell::new((None, None)) };
+}
+
+/// Ask days that draw their final state to save it to `path`, a `.png` or `.ppm` file.  The
+/// runner calls this for `--dump-image`.
+pub fn dump_to(path: impl Into<PathBuf>) -> Result<(), String> {
+    let path = path.into();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png" | "ppm") => {
+            DUMP.with(|d| *d.borrow_mut() = (Some(path), None));
+            Ok(())
+        }
+        _ => Err(format!(
+            "can't save an image to {}, use a .png or .ppm file",
+            path.display()
+        )),
+    }
+}
+
+/// Whether an image was asked for, so days can skip keeping state they'd only need to draw it.
+pub fn wants_dump() -> bool {
+    DUMP.with(|d| d.borrow().0.is_some())
+}
+
+/// Save the image `draw` returns, if one was asked for with [`dump_to`].
+pub fn dump(draw: impl FnOnce() -> Image) {
+    let Some(path) = DUMP.with(|d| d.borrow().0.clone()) else {
+        return;
+    };
+    let result = draw().save(&path);
+    DUMP.with(|d| d.borrow_mut().1 = Some(result));
+}
+
+/// How saving the image went, or `None` if the day never drew one.
+pub fn take_dump() -> Option<io::Result<()>> {
+    DUMP.with(|d| d.borrow_mut().1.take())
+}
+
+/// Encode `frames`, each shown for `delay`, as an animated PNG that loops forever.
+///
+/// All frames must have the same size and palette.  Repeated frames are merged into one longer
+/// frame.
+pub fn to_apng(frames: &[Image], delay: Duration) -> Vec<u8> {
+    let Some(first) = frames.first() else {
+        panic!("an animation needs at least one frame");
+    };
+    let steps = steps(frames);
+
+    let mut png = png_header(first);
+    let mut actl = Vec::new();
+    actl.extend_from_slice(&(steps.len() as u32).to_be_bytes());
+    actl.extend_from_slice(&0u32.to_be_bytes());
+    chunk(&mut png, b"acTL", &actl);
+
+    // fcTL and fdAT chunks share one sequence
+    let mut sequence = 0u32;
+    for (n, step) in steps.iter().enumerate() {
+        let image = &frames[step.frame];
+        let millis = (delay * step.repeats as u32)
+            .as_millis()
+            .min(u16::MAX as u128) as u16;
+
+        let mut fctl = Vec::new();
+        fctl.extend_from_slice(&sequence.to_be_bytes());
+        for value in [step.rect.w, step.rect.h, step.rect.x, step.rect.y] {
+            fctl.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        fctl.extend_from_slice(&millis.to_be_bytes());
+        fctl.extend_from_slice(&1000u16.to_be_bytes());
+        // dispose op none, blend op source
+        fctl.extend_from_slice(&[0, 0]);
+        chunk(&mut png, b"fcTL", &fctl);
+        sequence += 1;
+
+        let data = image.png_data(&step.rect);
+        if n == 0 {
+            chunk(&mut png, b"IDAT", &data);
+        } else {
+            let mut fdat = sequence.to_be_bytes().to_vec();
+            fdat.extend_from_slice(&data);
+            chunk(&mut png, b"fdAT", &fdat);
+            sequence += 1;
+        }
+    }
+
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Encode `frames`, each shown for `delay`, as an animated GIF that loops forever.
+///
+/// All frames must have the same size and palette.  Repeated frames are merged into one longer
+/// frame.  GIF delays are in hundredths of a second, so they're rounded so that the total running
+/// time stays right.
+///
+/// GIF sizes are 16 bits, so frames more than 65535 pixels across or down are an error.
+pub fn to_gif(frames: &[Image], delay: Duration) -> io::Result<Vec<u8>> {
+    let Some(first) = frames.first() else {
+        panic!("an animation needs at least one frame");
+    };
+    let (Ok(width), Ok(height)) = (u16::try_from(first.width), u16::try_from(first.height)) else {
+        return Err(io::Error::other(format!(
+            "a {}x{} animation is too big for a GIF, which can be at most 65535x65535",
+            first.width, first.height
+        )));
+    };
+
+    // the colour table size is a power of two, and LZW needs at least 2 bit codes
+    let bits = (first.palette.len().max(2) as u32)
+        .next_power_of_two()
+        .trailing_zeros()
+        .max(1);
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    gif.extend_from_slice(&[0xf0 | (bits - 1) as u8, 0, 0]);
+    for i in 0..1 << bits {
+        gif.extend_from_slice(first.palette.get(i).unwrap_or(&[0, 0, 0]));
+    }
+
+    // loop forever
+    gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    let centis = |frames: usize| (delay * frames as u32).as_millis().div_ceil(10);
+    let mut shown = 0;
+    for step in steps(frames) {
+        let image = &frames[step.frame];
+        let rect = &step.rect;
+        let delay = (centis(shown + step.repeats) - centis(shown)).min(u16::MAX as u128) as u16;
+        shown += step.repeats;
+
+        // graphic control extension: leave the frame in place, then wait
+        gif.extend_from_slice(&[0x21, 0xf9, 4, 0x04]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[0, 0]);
+
+        gif.push(0x2c);
+        for value in [rect.x, rect.y, rect.w, rect.h] {
+            let value = u16::try_from(value).expect("a frame's changes are inside the frame");
+            gif.extend_from_slice(&value.to_le_bytes());
+        }
+        gif.push(0);
+
+        let min_code_size = bits.max(2) as u8;
+        gif.push(min_code_size);
+        for block in lzw(min_code_size, &image.crop(rect)).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3b);
+    Ok(gif)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+}
+
+/// One frame of an animation as stored in a file: which frame, the part of it that changed, and
+/// how many frames' worth of time it stays up for.
+struct Step {
+    frame: usize,
+    rect: Rect,
+    repeats: usize,
+}
+
+fn steps(frames: &[Image]) -> Vec<Step> {
+    let first = &frames[0];
+    let mut steps = vec![Step {
+        frame: 0,
+        rect: first.bounds(),
+        repeats: 1,
+    }];
+
+    for (i, pair) in frames.windows(2).enumerate() {
+        let (prev, next) = (&pair[0], &pair[1]);
+        assert!(
+            next.width == first.width && next.height == first.height,
+            "animation frames must all be the same size"
+        );
+
+        match changed(prev, next) {
+            Some(rect) => steps.push(Step {
+                frame: i + 1,
+                rect,
+                repeats: 1,
+            }),
+            None => steps.last_mut().unwrap().repeats += 1,
+        }
+    }
+
+    steps
+}
+
+/// The smallest rectangle covering every pixel that differs, or `None` if nothing does.
+fn changed(prev: &Image, next: &Image) -> Option<Rect> {
+    let (mut x0, mut y0, mut x1, mut y1) = (usize::MAX, usize::MAX, 0, 0);
+
+    for y in 0..next.height {
+        let row = y * next.width..(y + 1) * next.width;
+        for (x, (a, b)) in prev.pixels[row.clone()]
+            .iter()
+            .zip(&next.pixels[row])
+            .enumerate()
+        {
+            if a != b {
+                x0 = x0.min(x);
+                y0 = y0.min(y);
+                x1 = x1.max(x);
+                y1 = y1.max(y);
+            }
+        }
+    }
+
+    (x0 != usize::MAX).then(|| Rect {
+        x: x0,
+        y: y0,
+        w: x1 - x0 + 1,
+        h: y1 - y0 + 1,
+    })
+}
+
+fn png_header(image: &Image) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&(image.width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(image.height as u32).to_be_bytes());
+    // 8 bit indexed colour, default compression, filtering and no interlacing
+    ihdr.extend_from_slice(&[8, 3, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &ihdr);
+
+    chunk(&mut png, b"PLTE", &image.palette.concat());
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &b| {
+        CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Writes codes least significant bit first, as both deflate and GIF's LZW expect.
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, len: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting from their most significant bit.
+    fn put_code(&mut self, code: u32, len: u32) {
+        self.put(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Compress `data` into a zlib stream.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    const WINDOW: usize = 32768;
+    const MAX_MATCH: usize = 258;
+
+    let mut out = BitWriter::default();
+    // final block, fixed Huffman codes
+    out.put(1, 1);
+    out.put(1, 2);
+
+    let hash = |i: usize| {
+        let key = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], 0]);
+        (key.wrapping_mul(2654435761) >> 17) as usize
+    };
+    // the most recent position each 3 byte prefix was seen at
+    let mut head = vec![usize::MAX; 1 << 15];
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut length = 0;
+        let mut distance = 0;
+
+        if i + 3 <= data.len() {
+            let h = hash(i);
+            let candidate = head[h];
+            head[h] = i;
+
+            if candidate != usize::MAX && i - candidate <= WINDOW {
+                length = data[i..]
+                    .iter()
+                    .zip(&data[candidate..])
+                    .take(MAX_MATCH)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                distance = i - candidate;
+            }
+        }
+
+        if length >= 3 {
+            put_match(&mut out, length, distance);
+            for j in i + 1..(i + length).min(data.len().saturating_sub(2)) {
+                head[hash(j)] = j;
+            }
+            i += length;
+        } else {
+            put_symbol(&mut out, data[i] as u16);
+            i += 1;
+        }
+    }
+    put_symbol(&mut out, 256);
+
+    let mut zlib = vec![0x78, 0x01];
+    zlib.extend(out.finish());
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+/// Write a literal/length symbol with the fixed Huffman code.
+fn put_symbol(out: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => out.put_code(0x30 + symbol, 8),
+        144..=255 => out.put_code(0x190 + symbol - 144, 9),
+        256..=279 => out.put_code(symbol - 256, 7),
+        _ => out.put_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn put_match(out: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    put_symbol(out, 257 + code as u16);
+    out.put(
+        (length - LENGTH_BASE[code] as usize) as u32,
+        LENGTH_EXTRA[code] as u32,
+    );
+
+    let code = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    out.put_code(code as u32, 5);
+    out.put(
+        (distance - DISTANCE_BASE[code] as usize) as u32,
+        DISTANCE_EXTRA[code] as u32,
+    );
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// GIF's variable width LZW, starting at `min_code_size + 1` bit codes.
+fn lzw(min_code_size: u8, pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u32 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = BitWriter::default();
+    let mut codes: HashMap<(u32, u8), u32> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_code_size as u32 + 1;
+
+    out.put(clear, width);
+
+    let Some((&first, rest)) = pixels.split_first() else {
+        out.put(end, width);
+        return out.finish();
+    };
+
+    let mut prefix = first as u32;
+    for &pixel in rest {
+        if let Some(&code) = codes.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        out.put(prefix, width);
+        if next < 4096 {
+            codes.insert((prefix, pixel), next);
+            next += 1;
+            // the decoder adds its entries one code later, so widen once it has caught up
+            if next > 1 << width {
+                width += 1;
+            }
+        } else {
+            out.put(clear, width);
+            codes.clear();
+            next = end + 1;
+            width = min_code_size as u32 + 1;
+        }
+        prefix = pixel as u32;
+    }
+
+    out.put(prefix, width);
+    out.put(end, width);
+    out.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard(palette: Vec<Rgb>) -> Image {
+        let mut image = Image::new(5, 3, palette);
+        for y in 0..3 {
+            for x in 0..5 {
+                image.set(x, y, ((x + y) % 2) as u8);
+            }
+        }
+        image
+    }
+
+    /// Check the chunk framing and CRCs, returning the chunk types in order.
+    fn png_chunks(png: &[u8]) -> Vec<String> {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc32(&rest[4..8 + len]), crc);
+            chunks.push(String::from_utf8(rest[4..8].to_vec()).unwrap());
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    #[test]
+    fn crc_adler_test() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn palette_test() {
+        let mut palette = Palette::new();
+        assert_eq!(palette.index([0, 0, 0]), 0);
+        assert_eq!(palette.index([255, 0, 0]), 1);
+        assert_eq!(palette.index([0, 0, 0]), 0);
+
+        for i in 2..256 {
+            palette.index([0, i as u8, 0]);
+        }
+        assert_eq!(palette.len(), 256);
+        // full, so the nearest colour is used
+        assert_eq!(palette.index([250, 1, 1]), 1);
+    }
+
+    #[test]
+    fn png_test() {
+        let png = checkerboard(vec![[0, 0, 0], [255, 255, 255]]).to_png();
+        assert_eq!(png_chunks(&png), ["IHDR", "PLTE", "IDAT", "IEND"]);
+
+        // the IDAT chunk follows the 8 byte signature, 25 byte IHDR and 18 byte PLTE
+        let len = u32::from_be_bytes(png[51..55].try_into().unwrap()) as usize;
+        let scanlines = miniz_oxide::inflate::decompress_to_vec_zlib(&png[59..59 + len]).unwrap();
+        assert_eq!(
+            scanlines,
+            [0, 0, 1, 0, 1, 0, 0, 1, 0, 1, 0, 1, 0, 0, 1, 0, 1, 0]
+        );
+    }
+
+    /// Noise from a small linear congruential generator, with values below `n`.
+    fn noise(len: usize, n: u8) -> Vec<u8> {
+        let mut seed = 1u32;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 16) as u8 % n
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zlib_test() {
+        // checked with an independent inflater, since the encoder can't vouch for itself
+        let inflate = |data: &[u8]| miniz_oxide::inflate::decompress_to_vec_zlib(&zlib(data));
+
+        assert_eq!(inflate(&[]).unwrap(), []);
+        assert_eq!(
+            inflate(b"abcabcabcabcabcabcx").unwrap(),
+            b"abcabcabcabcabcabcx"
+        );
+
+        let mut runs = vec![7; 100_000];
+        runs.extend(noise(100_000, 4));
+        runs.extend(vec![3; 300]);
+        assert_eq!(inflate(&runs).unwrap(), runs);
+    }
+
+    #[test]
+    fn grid_test() {
+        let image = Image::from_grid(3, 2, 2, vec![[0; 3], [255; 3]], |x, y| (x == y) as u8);
+        assert_eq!((image.width, image.height), (6, 4));
+        assert_eq!(image.get(1, 1), Some(1));
+        assert_eq!(image.get(2, 1), Some(0));
+        assert_eq!(image.get(3, 3), Some(1));
+
+        let ppm = image.to_ppm();
+        assert!(ppm.starts_with(b"P6\n6 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 6 * 4 * 3);
+        assert_eq!(&ppm[11..14], [255; 3]);
+    }
+
+    #[test]
+    fn distinct_test() {
+        let colors = distinct(12);
+        for (i, a) in colors.iter().enumerate() {
+            assert!(colors[i + 1..].iter().all(|b| a != b));
+        }
+    }
+
+    #[test]
+    fn dump_test() {
+        assert!(dump_to("grid.jpg").is_err());
+        assert!(!wants_dump());
+        dump(|| unreachable!());
+        assert!(take_dump().is_none());
+
+        let path = std::env::temp_dir().join(format!("dump_test_{}.ppm", std::process::id()));
+        dump_to(&path).unwrap();
+        assert!(wants_dump());
+        dump(|| Image::new(2, 2, vec![[1, 2, 3]]));
+        assert!(take_dump().unwrap().is_ok());
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            b"P6\n2 2\n255\n\x01\x02\x03\x01\x02\x03\x01\x02\x03\x01\x02\x03"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn apng_test() {
+        let first = checkerboard(vec![[0, 0, 0], [255, 255, 255]]);
+        let mut second = first.clone();
+        second.set(3, 2, 0);
+        second.set(4, 1, 0);
+
+        let steps = steps(&[first.clone(), first.clone(), second.clone()]);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].repeats, 2);
+        assert_eq!(
+            steps[1].rect,
+            Rect {
+                x: 3,
+                y: 1,
+                w: 2,
+                h: 2
+            }
+        );
+
+        let apng = to_apng(&[first.clone(), first, second], Duration::from_millis(100));
+        assert_eq!(
+            png_chunks(&apng),
+            ["IHDR", "PLTE", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "IEND"]
+        );
+    }
+
+    #[test]
+    fn gif_test() {
+        let first = checkerboard(vec![[0, 0, 0], [255, 255, 255], [255, 0, 0]]);
+        let mut second = first.clone();
+        second.set(0, 0, 2);
+
+        let gif = to_gif(&[first, second], Duration::from_millis(50)).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        // 5x3, with a 4 colour global table
+        assert_eq!(&gif[6..11], &[5, 0, 3, 0, 0xf1]);
+        assert_eq!(gif.last(), Some(&0x3b));
+
+        let wide = Image::new(70_000, 1, vec![[0; 3]]);
+        assert!(to_gif(&[wide], Duration::from_millis(50)).is_err());
+    }
+
+    /// Decode GIF LZW, to check the encoder against.
+    fn unlzw(min_code_size: u8, data: &[u8]) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> {
+            let mut table: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+            table.extend([vec![], vec![]]);
+            table
+        };
+
+        let mut table = reset();
+        let mut width = min_code_size as usize + 1;
+        let mut bit = 0;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+
+        loop {
+            let code = (0..width).fold(0, |code, i| {
+                let b = bit + i;
+                code | (((data[b / 8] >> (b % 8)) & 1) as usize) << i
+            });
+            bit += width;
+
+            if code == clear {
+                table = reset();
+                width = min_code_size as usize + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+
+            let entry = match (table.get(code), &prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => [prev.as_slice(), &prev[..1]].concat(),
+                (None, None) => panic!("unknown code {code}"),
+            };
+            out.extend_from_slice(&entry);
+
+            if let Some(prev) = prev {
+                table.push([prev.as_slice(), &entry[..1]].concat());
+                if table.len() == 1 << width && width < 12 {
+                    width += 1;
+                }
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_test() {
+        let pixels = [1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1];
+        assert_eq!(unlzw(2, &lzw(2, &pixels)), pixels);
+        assert_eq!(unlzw(2, &lzw(2, &[])), []);
+
+        // enough noise to fill the code table and force a clear
+        let noise = noise(50_000, 7);
+        assert_eq!(unlzw(3, &lzw(3, &noise)), noise);
+
+        // and the same with an independent decoder, which reads GIF's least significant bit first
+        let decode = |size, data: &[u8]| {
+            weezl::decode::Decoder::new(weezl::BitOrder::Lsb, size).decode(&lzw(size, data))
+        };
+        assert_eq!(decode(2, &pixels).unwrap(), pixels);
+        assert_eq!(decode(3, &noise).unwrap(), noise);
+    }
+}
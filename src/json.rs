@@ -0,0 +1,38 @@
+//! Just enough JSON to write strings, shared by the `--json` answers and the asciicast
+//! recordings.  It only uses `core`, so the `no_std` build's answers get the same escaping.
+
+use core::fmt::{self, Write};
+
+/// Displays a string as a quoted JSON string, escaping quotes, backslashes and control
+/// characters.
+#[derive(Debug, Clone, Copy)]
+pub struct Str<'a>(pub &'a str);
+
+impl fmt::Display for Str<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        for ch in self.0.chars() {
+            match ch {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                ch if ch < ' ' => write!(f, "\\u{:04x}", ch as u32)?,
+                ch => f.write_char(ch)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn str_test() {
+        assert_eq!(Str("a\"b\\c\x1b\r\n").to_string(), r#""a\"b\\c\u001b\r\n""#);
+        assert_eq!(Str("").to_string(), r#""""#);
+    }
+}
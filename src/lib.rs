@@ -1,9 +1,12 @@
+#![cfg_attr(all(feature = "no_std", not(test)), no_std)]
 #![allow(clippy::let_and_return)]
 #![allow(clippy::let_unit_value)]
 #![allow(clippy::too_many_arguments)]
 #![allow(unused)]
 #![feature(int_roundings)]
+#![feature(portable_simd)]
 #![feature(slice_group_by)]
+pub mod cycle;
 pub mod d1;
 pub mod d10;
 pub mod d11;
@@ -29,3 +32,13 @@ pub mod d6;
 pub mod d7;
 pub mod d8;
 pub mod d9;
+pub mod fixed;
+pub mod font;
+pub mod graph;
+pub mod image;
+pub mod json;
+pub mod nostd;
+pub mod record;
+pub mod trace;
+pub mod viz;
+pub mod vm;
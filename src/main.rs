@@ -1,6 +1,11 @@
 mod args;
+mod mem;
+mod report;
 
-use std::{fs::read_to_string, process::exit};
+use std::{fmt::Display, fs::read_to_string, process::exit};
+
+use args::Args;
+use report::{Phase, Report};
 
 fn main() {
     let args = args::parse_args().unwrap_or_else(|_| {
@@ -9,19 +14,25 @@ fn main() {
         std::process::exit(1);
     });
 
+    if args.mem {
+        mem::enable();
+    }
+
     // day 255 is a magic day number meaning "run all days"
     if args.day == 255 {
         for day in 1..=25 {
             let input =
                 read_to_string(format!("./input/d{}", day)).expect("couldn't read input file");
-            print!("day {day} part 1: ");
-            run(day, 1, input.trim_end().to_string());
-            print!("day {day} part 2: ");
-            run(day, 2, input.trim_end().to_string());
+            for part in 1..=2 {
+                if !args.json {
+                    print!("day {day} part {part}: ");
+                }
+                run(day, part, input.trim_end().to_string(), &args);
+            }
         }
     } else if (1..=25).contains(&args.day) {
-        let input_file = if args.input.is_some() {
-            args.input.unwrap()
+        let input_file = if let Some(input) = &args.input {
+            input.clone()
         } else if args.example {
             format!("./examples/d{}", args.day)
         } else {
@@ -29,7 +40,7 @@ fn main() {
         };
 
         if let Ok(input) = read_to_string(input_file) {
-            run(args.day, args.part, input);
+            run(args.day, args.part, input, &args);
         } else {
             eprintln!(
                 "Error: input file for day {} is missing or unreadable",
@@ -41,263 +52,177 @@ fn main() {
     }
 }
 
-fn run(day: u8, part: u8, input: String) {
+#[cfg(feature = "no_std")]
+fn run(day: u8, part: u8, input: String, args: &Args) {
+    use aoc2017::nostd;
+
     if ![1, 2].contains(&part) {
         eprintln!("Error: part must be 1 or 2");
         exit(1);
     }
 
-    match (day, part) {
-        (1, 1) => {
-            let parsed = aoc2017::d1::parse(input);
-            let output = aoc2017::d1::part1(parsed);
-            println!("{output}");
-        }
-        (1, 2) => {
-            let parsed = aoc2017::d1::parse(input);
-            let output = aoc2017::d1::part2(parsed);
-            println!("{output}");
-        }
-        (2, 1) => {
-            let parsed = aoc2017::d2::parse(input);
-            let output = aoc2017::d2::part1(parsed);
-            println!("{output}");
-        }
-        (2, 2) => {
-            let parsed = aoc2017::d2::parse(input);
-            let output = aoc2017::d2::part2(parsed);
-            println!("{output}");
-        }
-        (3, 1) => {
-            let parsed = aoc2017::d3::parse(input);
-            let output = aoc2017::d3::part1(parsed);
-            println!("{output}");
-        }
-        (3, 2) => {
-            let parsed = aoc2017::d3::parse(input);
-            let output = aoc2017::d3::part2(parsed);
-            println!("{output}");
-        }
-        (4, 1) => {
-            let parsed = aoc2017::d4::parse(input);
-            let output = aoc2017::d4::part1(parsed);
-            println!("{output}");
-        }
-        (4, 2) => {
-            let parsed = aoc2017::d4::parse(input);
-            let output = aoc2017::d4::part2(parsed);
-            println!("{output}");
-        }
-        (5, 1) => {
-            let parsed = aoc2017::d5::parse(input);
-            let output = aoc2017::d5::part1(parsed);
-            println!("{output}");
-        }
-        (5, 2) => {
-            let parsed = aoc2017::d5::parse(input);
-            let output = aoc2017::d5::part2(parsed);
-            println!("{output}");
-        }
-        (6, 1) => {
-            let parsed = aoc2017::d6::parse(input);
-            let output = aoc2017::d6::part1(parsed);
-            println!("{output}");
-        }
-        (6, 2) => {
-            let parsed = aoc2017::d6::parse(input);
-            let output = aoc2017::d6::part2(parsed);
-            println!("{output}");
-        }
-        (7, 1) => {
-            let parsed = aoc2017::d7::parse(input);
-            let output = aoc2017::d7::part1(parsed);
-            println!("{output}");
-        }
-        (7, 2) => {
-            let parsed = aoc2017::d7::parse(input);
-            let output = aoc2017::d7::part2(parsed);
-            println!("{output}");
-        }
-        (8, 1) => {
-            let parsed = aoc2017::d8::parse(input);
-            let output = aoc2017::d8::part1(parsed);
-            println!("{output}");
-        }
-        (8, 2) => {
-            let parsed = aoc2017::d8::parse(input);
-            let output = aoc2017::d8::part2(parsed);
-            println!("{output}");
-        }
-        (9, 1) => {
-            let parsed = aoc2017::d9::parse(input);
-            let output = aoc2017::d9::part1(parsed);
-            println!("{output}");
-        }
-        (9, 2) => {
-            let parsed = aoc2017::d9::parse(input);
-            let output = aoc2017::d9::part2(parsed);
-            println!("{output}");
-        }
-        (10, 1) => {
-            let parsed = aoc2017::d10::parse(input);
-            let output = aoc2017::d10::part1(parsed);
-            println!("{output}");
-        }
-        (10, 2) => {
-            let parsed = aoc2017::d10::parse(input);
-            let output = aoc2017::d10::part2(parsed);
-            println!("{output}");
-        }
-        (11, 1) => {
-            let parsed = aoc2017::d11::parse(input);
-            let output = aoc2017::d11::part1(parsed);
-            println!("{output}");
-        }
-        (11, 2) => {
-            let parsed = aoc2017::d11::parse(input);
-            let output = aoc2017::d11::part2(parsed);
-            println!("{output}");
-        }
-        (12, 1) => {
-            let parsed = aoc2017::d12::parse(input);
-            let output = aoc2017::d12::part1(parsed);
-            println!("{output}");
-        }
-        (12, 2) => {
-            let parsed = aoc2017::d12::parse(input);
-            let output = aoc2017::d12::part2(parsed);
-            println!("{output}");
-        }
-        (13, 1) => {
-            let parsed = aoc2017::d13::parse(input);
-            let output = aoc2017::d13::part1(parsed);
-            println!("{output}");
-        }
-        (13, 2) => {
-            let parsed = aoc2017::d13::parse(input);
-            let output = aoc2017::d13::part2(parsed);
-            println!("{output}");
-        }
-        (14, 1) => {
-            let parsed = aoc2017::d14::parse(input);
-            let output = aoc2017::d14::part1(parsed);
-            println!("{output}");
-        }
-        (14, 2) => {
-            let parsed = aoc2017::d14::parse(input);
-            let output = aoc2017::d14::part2(parsed);
-            println!("{output}");
-        }
-        (15, 1) => {
-            let parsed = aoc2017::d15::parse(input);
-            let output = aoc2017::d15::part1(parsed);
-            println!("{output}");
-        }
-        (15, 2) => {
-            let parsed = aoc2017::d15::parse(input);
-            let output = aoc2017::d15::part2(parsed);
-            println!("{output}");
-        }
-        (16, 1) => {
-            let parsed = aoc2017::d16::parse(input);
-            let output = aoc2017::d16::part1(parsed);
-            println!("{output}");
-        }
-        (16, 2) => {
-            let parsed = aoc2017::d16::parse(input);
-            let output = aoc2017::d16::part2(parsed);
-            println!("{output}");
-        }
-        (17, 1) => {
-            let parsed = aoc2017::d17::parse(input);
-            let output = aoc2017::d17::part1(parsed);
-            println!("{output}");
-        }
-        (17, 2) => {
-            let parsed = aoc2017::d17::parse(input);
-            let output = aoc2017::d17::part2(parsed);
-            println!("{output}");
-        }
-        (18, 1) => {
-            let parsed = aoc2017::d18::parse(input);
-            let output = aoc2017::d18::part1(parsed);
-            println!("{output}");
-        }
-        (18, 2) => {
-            let parsed = aoc2017::d18::parse(input);
-            let output = aoc2017::d18::part2(parsed);
-            println!("{output}");
-        }
-        (19, 1) => {
-            let parsed = aoc2017::d19::parse(input);
-            let output = aoc2017::d19::part1(parsed);
-            println!("{output}");
-        }
-        (19, 2) => {
-            let parsed = aoc2017::d19::parse(input);
-            let output = aoc2017::d19::part2(parsed);
-            println!("{output}");
-        }
-        (20, 1) => {
-            let parsed = aoc2017::d20::parse(input);
-            let output = aoc2017::d20::part1(parsed);
-            println!("{output}");
-        }
-        (20, 2) => {
-            let parsed = aoc2017::d20::parse(input);
-            let output = aoc2017::d20::part2(parsed);
-            println!("{output}");
-        }
-        (21, 1) => {
-            let parsed = aoc2017::d21::parse(input);
-            let output = aoc2017::d21::part1(parsed);
-            println!("{output}");
-        }
-        (21, 2) => {
-            let parsed = aoc2017::d21::parse(input);
-            let output = aoc2017::d21::part2(parsed);
-            println!("{output}");
-        }
-        (22, 1) => {
-            let parsed = aoc2017::d22::parse(input);
-            let output = aoc2017::d22::part1(parsed);
-            println!("{output}");
-        }
-        (22, 2) => {
-            let parsed = aoc2017::d22::parse(input);
-            let output = aoc2017::d22::part2(parsed);
-            println!("{output}");
-        }
-        (23, 1) => {
-            let parsed = aoc2017::d23::parse(input);
-            let output = aoc2017::d23::part1(parsed);
-            println!("{output}");
-        }
-        (23, 2) => {
-            let parsed = aoc2017::d23::parse(input);
-            let output = aoc2017::d23::part2(parsed);
-            println!("{output}");
-        }
-        (24, 1) => {
-            let parsed = aoc2017::d24::parse(input);
-            let output = aoc2017::d24::part1(parsed);
-            println!("{output}");
-        }
-        (24, 2) => {
-            let parsed = aoc2017::d24::parse(input);
-            let output = aoc2017::d24::part2(parsed);
-            println!("{output}");
-        }
-        (25, 1) => {
-            let parsed = aoc2017::d25::parse(input);
-            let output = aoc2017::d25::part1(parsed);
-            println!("{output}");
-        }
-        (25, 2) => {
-            let parsed = aoc2017::d25::parse(input);
-            let output = aoc2017::d25::part2(parsed);
-            println!("{output}");
-        }
+    if !nostd::DAYS.contains(&day) {
+        let days: Vec<String> = nostd::DAYS.iter().map(u8::to_string).collect();
+        eprintln!(
+            "Error: day {day} isn't available in the no_std build (supported days: {})",
+            days.join(", ")
+        );
+        return;
+    }
+
+    // the no_std days parse and solve in one go, so it's all counted as solving
+    let (answer, parse, solve) = solve(
+        input,
+        |input| input,
+        |input| {
+            nostd::solve(day, part, &input).unwrap_or_else(|err| {
+                eprintln!("Error: {err}");
+                exit(1);
+            })
+        },
+    );
+
+    Report {
+        day,
+        part,
+        answer,
+        parse,
+        solve,
+    }
+    .print(args);
+}
+
+#[cfg(not(feature = "no_std"))]
+fn run(day: u8, part: u8, input: String, args: &Args) {
+    use aoc2017::*;
+
+    if ![1, 2].contains(&part) {
+        eprintln!("Error: part must be 1 or 2");
+        exit(1);
+    }
+
+    // days with a visualization draw to the terminal (or a recording) while they run
+    #[cfg(feature = "visualize")]
+    match &args.record {
+        Some(path) => viz::install(record::Recorder::new(path, args.fps).unwrap_or_else(|err| {
+            eprintln!("Error: {err}");
+            exit(1);
+        })),
+        None => viz::install(viz::Terminal::new(args.fps)),
+    }
+    #[cfg(feature = "visualize")]
+    trace::set_breakpoints(
+        args.breakpoints
+            .iter()
+            .map(|spec| trace::Breakpoint::parse(spec))
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {err}");
+                exit(1);
+            }),
+    );
+
+    #[cfg(feature = "visualize")]
+    if let Some(projection) = &args.projection {
+        d20::set_projection(d20::Projection::parse(projection).unwrap_or_else(|err| {
+            eprintln!("Error: {err}");
+            exit(1);
+        }));
+    }
+
+    if let Some(path) = &args.dump_image {
+        image::dump_to(path).unwrap_or_else(|err| {
+            eprintln!("Error: {err}");
+            exit(1);
+        });
+    }
+
+    let (answer, parse, solve) = match (day, part) {
+        (1, 1) => solve(input, d1::parse, d1::part1),
+        (1, 2) => solve(input, d1::parse, d1::part2),
+        (2, 1) => solve(input, d2::parse, d2::part1),
+        (2, 2) => solve(input, d2::parse, d2::part2),
+        (3, 1) => solve(input, d3::parse, d3::part1),
+        (3, 2) => solve(input, d3::parse, d3::part2),
+        (4, 1) => solve(input, d4::parse, d4::part1),
+        (4, 2) => solve(input, d4::parse, d4::part2),
+        (5, 1) => solve(input, d5::parse, d5::part1),
+        (5, 2) => solve(input, d5::parse, d5::part2),
+        (6, 1) => solve(input, d6::parse, d6::part1),
+        (6, 2) => solve(input, d6::parse, d6::part2),
+        (7, 1) => solve(input, d7::parse, d7::part1),
+        (7, 2) => solve(input, d7::parse, d7::part2),
+        (8, 1) => solve(input, d8::parse, d8::part1),
+        (8, 2) => solve(input, d8::parse, d8::part2),
+        (9, 1) => solve(input, d9::parse, d9::part1),
+        (9, 2) => solve(input, d9::parse, d9::part2),
+        (10, 1) => solve(input, d10::parse, d10::part1),
+        (10, 2) => solve(input, d10::parse, d10::part2),
+        (11, 1) => solve(input, d11::parse, d11::part1),
+        (11, 2) => solve(input, d11::parse, d11::part2),
+        (12, 1) => solve(input, d12::parse, d12::part1),
+        (12, 2) => solve(input, d12::parse, d12::part2),
+        (13, 1) => solve(input, d13::parse, d13::part1),
+        (13, 2) => solve(input, d13::parse, d13::part2),
+        (14, 1) => solve(input, d14::parse, d14::part1),
+        (14, 2) => solve(input, d14::parse, d14::part2),
+        (15, 1) => solve(input, d15::parse, d15::part1),
+        (15, 2) => solve(input, d15::parse, d15::part2),
+        (16, 1) => solve(input, d16::parse, d16::part1),
+        (16, 2) => solve(input, d16::parse, d16::part2),
+        (17, 1) => solve(input, d17::parse, d17::part1),
+        (17, 2) => solve(input, d17::parse, d17::part2),
+        (18, 1) => solve(input, d18::parse, d18::part1),
+        (18, 2) => solve(input, d18::parse, d18::part2),
+        (19, 1) => solve(input, d19::parse, d19::part1),
+        (19, 2) => solve(input, d19::parse, d19::part2),
+        (20, 1) => solve(input, d20::parse, d20::part1),
+        (20, 2) => solve(input, d20::parse, d20::part2),
+        (21, 1) => solve(input, d21::parse, d21::part1),
+        (21, 2) => solve(input, d21::parse, d21::part2),
+        (22, 1) => solve(input, d22::parse, d22::part1),
+        (22, 2) => solve(input, d22::parse, d22::part2),
+        (23, 1) => solve(input, d23::parse, d23::part1),
+        (23, 2) => solve(input, d23::parse, d23::part2),
+        (24, 1) => solve(input, d24::parse, d24::part1),
+        (24, 2) => solve(input, d24::parse, d24::part2),
+        (25, 1) => solve(input, d25::parse, d25::part1),
+        (25, 2) => solve(input, d25::parse, d25::part2),
         _ => unimplemented!(),
+    };
+
+    // hand the terminal back, or save the recording, before printing the answer
+    #[cfg(feature = "visualize")]
+    if let Some(Err(err)) = viz::uninstall().map(|mut sink| sink.finish()) {
+        eprintln!("Error: couldn't save the recording: {err}");
+    }
+
+    match (&args.dump_image, image::take_dump()) {
+        (Some(path), Some(Err(err))) => eprintln!("Error: couldn't save {path}: {err}"),
+        (Some(_), None) if args.day != 255 => {
+            eprintln!("Error: day {day} doesn't draw an image to save")
+        }
+        _ => {}
     }
+
+    Report {
+        day,
+        part,
+        answer,
+        parse,
+        solve,
+    }
+    .print(args);
+}
+
+/// Parse the input and solve one part, measuring each phase.
+fn solve<M, A: Display>(
+    input: String,
+    parse: impl FnOnce(String) -> M,
+    part: impl FnOnce(M) -> A,
+) -> (String, Phase, Phase) {
+    let (model, parse) = report::phase(|| parse(input));
+    let (answer, solve) = report::phase(|| part(model));
+
+    (answer.to_string(), parse, solve)
 }
@@ -0,0 +1,102 @@
+//! A global allocator that counts allocations, for `-m/--mem`.
+//!
+//! It forwards everything to the system allocator.  Counting is switched off until
+//! [`enable`] is called, so runs without `-m` only pay for one relaxed atomic load per
+//! allocation.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed};
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+impl Counting {
+    fn grow(&self, size: usize) {
+        ALLOCS.fetch_add(1, Relaxed);
+        BYTES.fetch_add(size, Relaxed);
+        let live = LIVE.fetch_add(size, Relaxed) + size;
+        PEAK.fetch_max(live, Relaxed);
+    }
+
+    fn shrink(&self, size: usize) {
+        // saturate, since blocks allocated before counting was enabled can be freed later
+        let _ = LIVE.fetch_update(Relaxed, Relaxed, |live| Some(live.saturating_sub(size)));
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() && ENABLED.load(Relaxed) {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() && ENABLED.load(Relaxed) {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        if ENABLED.load(Relaxed) {
+            self.shrink(layout.size());
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() && ENABLED.load(Relaxed) {
+            // count a reallocation as freeing the old block and allocating the new one
+            self.shrink(layout.size());
+            self.grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Allocation activity during some stretch of code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    /// Number of allocations (reallocations included).
+    pub allocs: usize,
+    /// Total bytes requested.
+    pub bytes: usize,
+    /// Highest number of live bytes, above what was live at the start.
+    pub peak: usize,
+}
+
+/// Start counting allocations.
+pub fn enable() {
+    ENABLED.store(true, Relaxed);
+}
+
+/// Run `f` and report the allocations it made.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Stats) {
+    let live = LIVE.load(Relaxed);
+    let allocs = ALLOCS.load(Relaxed);
+    let bytes = BYTES.load(Relaxed);
+    PEAK.store(live, Relaxed);
+
+    let out = f();
+
+    let stats = Stats {
+        allocs: ALLOCS.load(Relaxed) - allocs,
+        bytes: BYTES.load(Relaxed) - bytes,
+        peak: PEAK.load(Relaxed).saturating_sub(live),
+    };
+
+    (out, stats)
+}
@@ -0,0 +1,64 @@
+//! Entry points for the allocation-free build.
+//!
+//! With the `no_std` feature enabled the library drops `std`, and only the days listed in
+//! [`DAYS`] are compiled with a usable solution.  Those days solve straight from the input `&str`
+//! using fixed-capacity collections from [`crate::fixed`].
+
+use core::fmt;
+
+use crate::{d1, d2, d6};
+
+/// Days that can be solved without `std` or an allocator.
+pub const DAYS: &[u8] = &[1, 2, 6];
+
+/// Why [`solve`] has no answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The day isn't one of [`DAYS`].
+    Unavailable,
+    /// The input has something the day can't read, or an answer too big for it.
+    BadInput,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "the day isn't available without std"),
+            Self::BadInput => write!(f, "the input isn't valid, or its answer is too big"),
+        }
+    }
+}
+
+/// Solve one part of a day without allocating.
+pub fn solve(day: u8, part: u8, input: &str) -> Result<u64, Error> {
+    let answer = match (day, part) {
+        (1, 1) => d1::part1_str(input).ok_or(Error::BadInput)?,
+        (1, 2) => d1::part2_str(input).ok_or(Error::BadInput)?,
+        (2, 1) => d2::part1_str(input).ok_or(Error::BadInput)?.into(),
+        (2, 2) => d2::part2_str(input).ok_or(Error::BadInput)?.into(),
+        (6, 1) => d6::part1_str(input).ok_or(Error::BadInput)? as u64,
+        (6, 2) => d6::part2_str(input).ok_or(Error::BadInput)? as u64,
+        _ => return Err(Error::Unavailable),
+    };
+
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_test() {
+        assert_eq!(solve(1, 1, include_str!("../input/d1")), Ok(1393));
+        assert_eq!(solve(2, 2, include_str!("../input/d2")), Ok(275));
+        assert_eq!(solve(6, 2, include_str!("../input/d6")), Ok(2793));
+        assert_eq!(
+            solve(7, 1, include_str!("../input/d7")),
+            Err(Error::Unavailable)
+        );
+        assert_eq!(solve(1, 1, "aa"), Err(Error::BadInput));
+        assert_eq!(solve(2, 1, "5 1 9 x"), Err(Error::BadInput));
+        assert_eq!(solve(6, 1, "0 2 x 0"), Err(Error::BadInput));
+    }
+}
@@ -0,0 +1,317 @@
+//! Recording visualizations to files with `--record`, so they can be shared without a screen
+//! capture.
+//!
+//! The file extension picks the format:
+//!
+//!  - `.cast` is an asciinema v2 recording, which keeps the text and colours as they were,
+//!  - `.gif` is an animated GIF,
+//!  - `.png` or `.apng` is an animated PNG.
+//!
+//! The image formats draw each character cell with the bitmap [`font`].
+
+#![cfg(all(feature = "visualize", any(test, not(feature = "no_std"))))]
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
+
+use crate::font::{self, Shape};
+use crate::image::{self, Image, Palette};
+use crate::json;
+use crate::viz::{Color, Frame, Style, Visualize};
+
+/// Image pixels per font pixel.
+const SCALE: usize = 2;
+/// A character cell in font pixels: the glyph plus a column and two rows of spacing.
+const CELL_WIDTH: usize = font::WIDTH + 1;
+const CELL_HEIGHT: usize = font::HEIGHT + 2;
+
+/// Colours for cells without one, matching a dark terminal.
+const FOREGROUND: Color = Color::White;
+const BACKGROUND: Color = Color::Black;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Cast,
+    Gif,
+    Apng,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "cast" => Some(Self::Cast),
+            "gif" => Some(Self::Gif),
+            "png" | "apng" => Some(Self::Apng),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps every frame, then writes them all to a file when finished.
+pub struct Recorder {
+    path: PathBuf,
+    format: Format,
+    delay: Duration,
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    /// Record to `path`, playing back at `fps` frames per second.
+    pub fn new(path: impl Into<PathBuf>, fps: u32) -> Result<Self, String> {
+        let path = path.into();
+        let format = Format::from_path(&path).ok_or_else(|| {
+            format!(
+                "can't record to {}, use a .cast, .gif or .png file",
+                path.display()
+            )
+        })?;
+
+        Ok(Self {
+            path,
+            format,
+            delay: Duration::from_secs(1) / fps.max(1),
+            frames: Vec::new(),
+        })
+    }
+}
+
+impl Visualize for Recorder {
+    fn frame(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.frames.is_empty() {
+            return Err(io::Error::other(
+                "nothing was drawn, so there's nothing to record",
+            ));
+        }
+
+        let bytes = match self.format {
+            Format::Cast => cast(&self.frames, self.delay).into_bytes(),
+            Format::Gif => image::to_gif(&render_all(&self.frames), self.delay)?,
+            Format::Apng => image::to_apng(&render_all(&self.frames), self.delay),
+        };
+
+        fs::write(&self.path, bytes)
+    }
+}
+
+/// The frames as an asciinema v2 recording.
+pub fn cast(frames: &[Frame], delay: Duration) -> String {
+    let (width, height) = size(frames);
+    let mut out = format!(
+        r#"{{"version": 2, "width": {width}, "height": {height}, "env": {{"TERM": "xterm-256color"}}}}"#
+    );
+    out.push('\n');
+
+    for (i, frame) in frames.iter().enumerate() {
+        let ansi = frame.to_ansi();
+        // the player emulates a terminal rather than a tty, so newlines need carriage returns,
+        // and the last one would scroll the screen
+        let lines = ansi
+            .strip_suffix('\n')
+            .unwrap_or(&ansi)
+            .replace('\n', "\r\n");
+        let screen = format!("\x1b[H{lines}\x1b[J");
+
+        let time = (delay * i as u32).as_secs_f64();
+        let _ = writeln!(out, "[{time:.6}, \"o\", {}]", json::Str(&screen));
+    }
+
+    // keep the last frame up for as long as the others
+    let end = (delay * frames.len() as u32).as_secs_f64();
+    let _ = writeln!(out, "[{end:.6}, \"o\", \"\"]");
+    out
+}
+
+/// Draw the frames as images of the same size, sharing one palette.
+pub fn render_all(frames: &[Frame]) -> Vec<Image> {
+    let (width, height) = size(frames);
+    let mut palette = Palette::new();
+    palette.index(BACKGROUND.rgb().into());
+
+    let mut images: Vec<Image> = frames
+        .iter()
+        .map(|frame| render(frame, width, height, &mut palette))
+        .collect();
+
+    // the palette only grows, so earlier frames' indices still hold
+    for image in &mut images {
+        image.palette = palette.colors().to_vec();
+    }
+    images
+}
+
+/// Draw one frame on a `width` by `height` character canvas.  The palette's first colour must be
+/// the background.
+pub fn render(frame: &Frame, width: usize, height: usize, palette: &mut Palette) -> Image {
+    let mut image = Image::new(
+        width * CELL_WIDTH * SCALE,
+        height * CELL_HEIGHT * SCALE,
+        vec![[0; 3]],
+    );
+
+    for (y, row) in frame.rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            draw(&mut image, x, y, cell.ch, cell.style, palette);
+        }
+    }
+
+    for (i, line) in frame.status.iter().enumerate() {
+        for (x, ch) in line.chars().enumerate() {
+            draw(
+                &mut image,
+                x,
+                frame.height() + i,
+                ch,
+                Style::default(),
+                palette,
+            );
+        }
+    }
+
+    image.palette = palette.colors().to_vec();
+    image
+}
+
+/// Draw `ch` in the character cell at (x, y).
+fn draw(image: &mut Image, x: usize, y: usize, ch: char, style: Style, palette: &mut Palette) {
+    let fg = palette.index(style.fg.unwrap_or(FOREGROUND).rgb().into());
+    let bg = palette.index(style.bg.unwrap_or(BACKGROUND).rgb().into());
+
+    // cell origin, in font pixels
+    let (x0, y0) = (x * CELL_WIDTH, y * CELL_HEIGHT);
+    let mut plot = |x: usize, y: usize, color: u8| {
+        image.fill(x * SCALE, y * SCALE, SCALE, SCALE, color);
+    };
+
+    for y in y0..y0 + CELL_HEIGHT {
+        for x in x0..x0 + CELL_WIDTH {
+            plot(x, y, bg);
+        }
+    }
+
+    match font::shape(ch) {
+        Shape::Glyph(mut glyph) => {
+            if style.bold {
+                // smear each row one pixel to the right
+                glyph = glyph.map(|row| row | row >> 1);
+            }
+            if style.strike {
+                glyph[font::HEIGHT / 2] = 0x1f;
+            }
+
+            for gy in 0..font::HEIGHT {
+                for gx in 0..font::WIDTH {
+                    if font::pixel(&glyph, gx, gy) {
+                        plot(x0 + gx, y0 + 1 + gy, fg);
+                    }
+                }
+            }
+        }
+        Shape::Block => {
+            for y in y0..y0 + CELL_HEIGHT {
+                for x in x0..x0 + CELL_WIDTH {
+                    plot(x, y, fg);
+                }
+            }
+        }
+        Shape::Lines([up, right, down, left]) => {
+            // the middle of the glyph, where the lines meet
+            let (mx, my) = (x0 + font::WIDTH / 2, y0 + 1 + font::HEIGHT / 2);
+
+            let ys = (if up { y0 } else { my })..=(if down { y0 + CELL_HEIGHT - 1 } else { my });
+            for y in ys {
+                plot(mx, y, fg);
+            }
+
+            let xs = (if left { x0 } else { mx })..=(if right { x0 + CELL_WIDTH - 1 } else { mx });
+            for x in xs {
+                plot(x, my, fg);
+            }
+        }
+    }
+}
+
+/// The size in characters that fits every frame, status lines included.
+fn size(frames: &[Frame]) -> (usize, usize) {
+    frames.iter().fold((1, 1), |(width, height), frame| {
+        let status_width = frame.status.iter().map(|line| line.chars().count());
+        (
+            status_width.fold(width.max(frame.width()), usize::max),
+            height.max(frame.height() + frame.status.len()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames() -> Vec<Frame> {
+        (0..3)
+            .map(|i| {
+                let mut frame = Frame::new(3, 2);
+                frame.set(i, 0, '#', Style::fg(Color::Red));
+                frame.set(i, 1, '│', Style::default());
+                frame.status(format!("step {i}"));
+                frame
+            })
+            .collect()
+    }
+
+    #[test]
+    fn format_test() {
+        assert_eq!(Format::from_path(Path::new("d3.cast")), Some(Format::Cast));
+        assert_eq!(
+            Format::from_path(Path::new("out/d3.png")),
+            Some(Format::Apng)
+        );
+        assert_eq!(Format::from_path(Path::new("d3.mp4")), None);
+        assert!(Recorder::new("d3", 30).is_err());
+    }
+
+    #[test]
+    fn cast_test() {
+        let cast = cast(&frames(), Duration::from_millis(500));
+        let lines: Vec<&str> = cast.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            r#"{"version": 2, "width": 6, "height": 3, "env": {"TERM": "xterm-256color"}}"#
+        );
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].starts_with(r#"[0.500000, "o", "\u001b[H \u001b[0;31m#"#));
+        assert!(lines[2].ends_with(r#"\u001b[K\r\nstep 1\u001b[K\u001b[J"]"#));
+        assert_eq!(lines[4], r#"[1.500000, "o", ""]"#);
+    }
+
+    #[test]
+    fn render_test() {
+        let images = render_all(&frames());
+        let image = &images[0];
+
+        // 6 by 3 cells
+        assert_eq!(image.width, 6 * CELL_WIDTH * SCALE);
+        assert_eq!(image.height, 3 * CELL_HEIGHT * SCALE);
+        assert_eq!(image.palette, images[2].palette);
+
+        let color = |x: usize, y: usize| image.palette[image.get(x, y).unwrap() as usize];
+        let red: [u8; 3] = Color::Red.rgb().into();
+        let white: [u8; 3] = FOREGROUND.rgb().into();
+        let black: [u8; 3] = BACKGROUND.rgb().into();
+
+        // the spacing above the '#' in the first cell, then its top row
+        assert_eq!(color(SCALE, 0), black);
+        assert_eq!(color(SCALE, SCALE), red);
+
+        // the line in the cell below reaches both its top and bottom edges
+        let mid = font::WIDTH / 2 * SCALE;
+        assert_eq!(color(mid, CELL_HEIGHT * SCALE), white);
+        assert_eq!(color(mid, 2 * CELL_HEIGHT * SCALE - 1), white);
+        assert_eq!(color(0, CELL_HEIGHT * SCALE), black);
+    }
+}
@@ -0,0 +1,153 @@
+//! Printing answers along with the optional timing (`-t`) and memory (`-m`) stats, as text or
+//! JSON (`-j`).
+
+use std::time::{Duration, Instant};
+
+use aoc2017::json;
+
+use crate::args::Args;
+use crate::mem;
+
+/// Time and allocations spent in one phase of a run (parsing, or solving a part).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Phase {
+    pub time: Duration,
+    pub mem: mem::Stats,
+}
+
+/// Run `f`, recording how long it took and what it allocated.
+pub fn phase<T>(f: impl FnOnce() -> T) -> (T, Phase) {
+    let start = Instant::now();
+    let (out, mem) = mem::measure(f);
+    let time = start.elapsed();
+
+    (out, Phase { time, mem })
+}
+
+/// The outcome of running one part of one day.
+pub struct Report {
+    pub day: u8,
+    pub part: u8,
+    pub answer: String,
+    pub parse: Phase,
+    pub solve: Phase,
+}
+
+impl Report {
+    pub fn print(&self, args: &Args) {
+        if args.json {
+            println!("{}", self.to_json(args));
+            return;
+        }
+
+        println!("{}", self.answer);
+
+        if args.time || args.mem {
+            println!("  parse: {}", describe(&self.parse, args));
+            println!("  part {}: {}", self.part, describe(&self.solve, args));
+        }
+    }
+
+    fn to_json(&self, args: &Args) -> String {
+        format!(
+            r#"{{"day":{},"part":{},"answer":{},"parse":{},"solve":{}}}"#,
+            self.day,
+            self.part,
+            json::Str(&self.answer),
+            phase_json(&self.parse, args),
+            phase_json(&self.solve, args),
+        )
+    }
+}
+
+fn describe(phase: &Phase, args: &Args) -> String {
+    let mut fields = Vec::new();
+
+    if args.time {
+        fields.push(format!("{:?}", phase.time));
+    }
+
+    if args.mem {
+        fields.push(format!("{} allocs", phase.mem.allocs));
+        fields.push(format!("{} allocated", bytes(phase.mem.bytes)));
+        fields.push(format!("{} peak", bytes(phase.mem.peak)));
+    }
+
+    fields.join(", ")
+}
+
+fn phase_json(phase: &Phase, args: &Args) -> String {
+    let mut json = format!(r#"{{"nanos":{}"#, phase.time.as_nanos());
+
+    if args.mem {
+        json += &format!(
+            r#","allocs":{},"bytes":{},"peak_bytes":{}"#,
+            phase.mem.allocs, phase.mem.bytes, phase.mem.peak
+        );
+    }
+
+    json + "}"
+}
+
+/// Format a byte count with a binary unit.
+fn bytes(n: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = n as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{n} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_test() {
+        assert_eq!(bytes(0), "0 B");
+        assert_eq!(bytes(1023), "1023 B");
+        assert_eq!(bytes(1536), "1.5 KiB");
+        assert_eq!(bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn measure_test() {
+        mem::enable();
+
+        let (v, phase) = phase(|| vec![0u8; 4096]);
+
+        assert_eq!(v.len(), 4096);
+        assert!(phase.mem.allocs >= 1);
+        assert!(phase.mem.bytes >= 4096);
+        assert!(phase.mem.peak >= 4096);
+    }
+
+    #[test]
+    fn json_test() {
+        let report = Report {
+            day: 8,
+            part: 1,
+            answer: "#..\n.#\"\n".to_string(),
+            parse: Phase::default(),
+            solve: Phase::default(),
+        };
+        let args = Args {
+            json: true,
+            ..Args::default()
+        };
+
+        assert_eq!(
+            report.to_json(&args),
+            r##"{"day":8,"part":1,"answer":"#..\n.#\"\n","parse":{"nanos":0},"solve":{"nanos":0}}"##
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! A step-by-step view of a register [`Machine`], for `just viz` on days 8, 18 and 23.
+//!
+//! The program listing is on the left, with the current instruction highlighted and each
+//! instruction's hit count shaded as a heat map.  The registers are on the right, flashing when
+//! they've changed since the last frame.  Breakpoints, given with `--break 12` or
+//! `--break "a > 5"`, pause the animation when the program counter reaches an instruction or a
+//! register condition becomes true.
+
+#![cfg(all(feature = "visualize", any(test, not(feature = "no_std"))))]
+
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::viz::{self, Color, Frame, Style};
+use crate::vm::{Cmp, Instruction, Io, Machine, State};
+
+/// Instructions listed at once.  Longer programs scroll to follow the program counter.
+const LISTING: usize = 24;
+/// Every step is drawn at first, then roughly one frame per this many steps so far, so that long
+/// runs still finish.
+const DETAIL: u64 = 100;
+/// Hit counts from cold to hot.
+const HEAT: [Color; 6] = [
+    Color::Gray,
+    Color::Blue,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Red,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop whenever the program counter reaches this instruction.
+    At(usize),
+    /// Stop when a register comparison becomes true.
+    When(String, Cmp, i64),
+}
+
+impl Breakpoint {
+    /// Parse an instruction number (`12`) or a register condition (`a > 5`, `b!=0`).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let error = || {
+            format!(
+                "bad breakpoint \"{spec}\", expected an instruction number or a condition like \"a > 5\""
+            )
+        };
+
+        if let Ok(pc) = spec.trim().parse() {
+            return Ok(Self::At(pc));
+        }
+
+        let is_op = |c: char| "<>=!".contains(c);
+        let start = spec.find(is_op).ok_or_else(error)?;
+        let end = spec[start..]
+            .find(|c| !is_op(c))
+            .map_or(spec.len(), |len| start + len);
+
+        let reg = spec[..start].trim();
+        match (
+            reg,
+            Cmp::parse(&spec[start..end]),
+            spec[end..].trim().parse(),
+        ) {
+            ("", ..) => Err(error()),
+            (reg, Some(cmp), Ok(value)) => Ok(Self::When(reg.to_string(), cmp, value)),
+            _ => Err(error()),
+        }
+    }
+
+    fn holds<I>(&self, vm: &Machine<I>) -> bool {
+        match self {
+            Self::At(pc) => vm.pc == *pc as i64,
+            Self::When(reg, cmp, value) => vm
+                .registers
+                .by_name(reg)
+                .is_some_and(|current| cmp.eval(current, *value)),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::At(pc) => write!(f, "instruction {pc}"),
+            Self::When(reg, cmp, value) => write!(f, "{reg} {} {value}", cmp.as_str()),
+        }
+    }
+}
+
+thread_local! {
+    static BREAKPOINTS: RefCell<Vec<Breakpoint>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Set the breakpoints that tracers created on this thread start with.  The runner calls this
+/// with the `--break` arguments.
+pub fn set_breakpoints(breakpoints: Vec<Breakpoint>) {
+    BREAKPOINTS.with(|b| *b.borrow_mut() = breakpoints);
+}
+
+/// Draws a machine as it runs.  Call [`Tracer::observe`] after each step, or let
+/// [`Tracer::run`] do the stepping.
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    breakpoints: Vec<Breakpoint>,
+    /// Whether each breakpoint held after the previous step, so conditions only break when they
+    /// become true.
+    held: Vec<bool>,
+    /// Register values in the last frame, to flash the ones that changed.
+    shown: Vec<i64>,
+    /// The step count at which the next frame is due.
+    next_frame: u64,
+    /// The step count of the last frame, so a stopped machine isn't drawn over and over.
+    last_frame: Option<u64>,
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tracer {
+    /// A tracer using the breakpoints given to [`set_breakpoints`].
+    pub fn new() -> Self {
+        Self::with_breakpoints(BREAKPOINTS.with(|b| b.borrow().clone()))
+    }
+
+    pub fn with_breakpoints(breakpoints: Vec<Breakpoint>) -> Self {
+        Self {
+            held: vec![false; breakpoints.len()],
+            breakpoints,
+            shown: Vec::new(),
+            next_frame: 0,
+            last_frame: None,
+        }
+    }
+
+    /// Run the machine like [`Machine::run`], drawing it along the way.
+    pub fn run<I: Instruction>(&mut self, vm: &mut Machine<I>, io: &mut dyn Io) -> State {
+        self.observe(vm);
+        loop {
+            let state = vm.step(io);
+            self.observe(vm);
+            if state == State::Blocked && viz::active() && self.last_frame != Some(vm.steps) {
+                self.draw(vm, Some("waiting for input".to_string()));
+            }
+            if state != State::Running {
+                return state;
+            }
+        }
+    }
+
+    /// Look at the machine after a step, drawing it if a frame is due or a breakpoint was hit.
+    pub fn observe<I: Instruction>(&mut self, vm: &Machine<I>) {
+        if !viz::active() {
+            return;
+        }
+
+        let mut hit = Vec::new();
+        for (breakpoint, held) in self.breakpoints.iter().zip(&mut self.held) {
+            let holds = breakpoint.holds(vm);
+            let repeat = *held && matches!(breakpoint, Breakpoint::When(..));
+            if holds && !repeat {
+                hit.push(breakpoint.to_string());
+            }
+            *held = holds;
+        }
+
+        if !hit.is_empty() {
+            viz::pause();
+            self.draw(vm, Some(format!("breakpoint: {}", hit.join(", "))));
+        } else if self.last_frame != Some(vm.steps)
+            && (vm.steps >= self.next_frame || vm.is_halted())
+        {
+            self.draw(vm, None);
+        }
+    }
+
+    fn draw<I: Instruction>(&mut self, vm: &Machine<I>, note: Option<String>) {
+        self.last_frame = Some(vm.steps);
+        self.next_frame = vm.steps + (vm.steps / DETAIL).max(1);
+
+        let listing: Vec<String> = vm
+            .program
+            .iter()
+            .map(|inst| inst.describe(&vm.registers))
+            .collect();
+        let hits = vm.hits();
+        let max_hits = hits.iter().copied().max().unwrap_or(0);
+
+        let text_width = listing.iter().map(String::len).max().unwrap_or(0);
+        let shown = listing.len().min(LISTING);
+        // keep the program counter in the middle of the listing, where possible
+        let first = usize::try_from(vm.pc)
+            .unwrap_or(0)
+            .saturating_sub(shown / 2)
+            .min(listing.len() - shown);
+
+        // breakpoint marker, index, the instruction, then its hit count and heat
+        let hits_x = 7 + text_width + 1;
+        let regs_x = hits_x + 9;
+
+        let names: Vec<(&str, i64)> = vm.registers.iter().collect();
+        let reg_width = names
+            .iter()
+            .map(|(name, value)| name.len() + 1 + value.to_string().len())
+            .max()
+            .unwrap_or(0)
+            + 2;
+        let reg_rows = shown.max(1);
+        let reg_cols = names.len().div_ceil(reg_rows);
+
+        let mut frame = Frame::new(regs_x + reg_cols * reg_width, shown.max(reg_rows));
+
+        for row in 0..shown {
+            let i = first + row;
+            let current = vm.pc == i as i64;
+
+            if self.breakpoints.contains(&Breakpoint::At(i)) {
+                frame.set(0, row, '●', Style::fg(Color::Red));
+            }
+            frame.print(1, row, &format!("{i:>4}"), Style::fg(Color::Gray));
+
+            let style = match current {
+                true => Style::bg(Color::Blue).bold(),
+                false => Style::default(),
+            };
+            let text = format!(" {:<text_width$} ", listing[i]);
+            frame.print(6, row, &text, style);
+
+            // hit counts on a log scale, so hot loops don't wash everything else out
+            let heat = match (hits[i], max_hits) {
+                (0, _) => 0,
+                (n, max) => {
+                    let scale = (n as f64).ln_1p() / (max as f64).ln_1p();
+                    1 + (scale * (HEAT.len() - 2) as f64).round() as usize
+                }
+            };
+            let style = Style::fg(HEAT[heat]);
+            frame.print(hits_x, row, &format!("{:>6} ", count(hits[i])), style);
+            frame.set(hits_x + 7, row, '█', style);
+        }
+
+        for (n, &(name, value)) in names.iter().enumerate() {
+            let changed = self.shown.get(n).is_some_and(|&old| old != value);
+            let style = match changed {
+                true => Style {
+                    fg: Some(Color::Black),
+                    bg: Some(Color::Yellow),
+                    ..Style::default()
+                },
+                false => Style::default(),
+            };
+
+            let (col, row) = (n / reg_rows, n % reg_rows);
+            frame.print(
+                regs_x + col * reg_width,
+                row,
+                &format!("{name} {value}"),
+                style,
+            );
+        }
+        self.shown = names.iter().map(|&(_, value)| value).collect();
+
+        let state = if vm.is_halted() { "halted" } else { "running" };
+        frame.status(format!("step {}, pc {}, {state}", vm.steps, vm.pc));
+        let counts: Vec<String> = vm.counts().map(|(op, n)| format!("{op} {n}")).collect();
+        frame.status(counts.join("  "));
+        if let Some(note) = note {
+            frame.status(note);
+        }
+
+        viz::emit(frame);
+    }
+}
+
+/// A hit count in at most six characters.
+fn count(n: u64) -> String {
+    match n {
+        0..=99_999 => n.to_string(),
+        100_000..=99_999_999 => format!("{}k", n / 1000),
+        _ => format!("{}M", n / 1_000_000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Asm, Registers};
+
+    #[test]
+    fn breakpoint_parse_test() {
+        assert_eq!(Breakpoint::parse("12"), Ok(Breakpoint::At(12)));
+        assert_eq!(
+            Breakpoint::parse("a > 5"),
+            Ok(Breakpoint::When("a".to_string(), Cmp::Gt, 5))
+        );
+        assert_eq!(
+            Breakpoint::parse("foo!=-3"),
+            Ok(Breakpoint::When("foo".to_string(), Cmp::Ne, -3))
+        );
+        assert!(Breakpoint::parse("> 5").is_err());
+        assert!(Breakpoint::parse("a => 5").is_err());
+        assert!(Breakpoint::parse("a").is_err());
+        assert_eq!(Breakpoint::parse("b <= 2").unwrap().to_string(), "b <= 2");
+    }
+
+    #[test]
+    fn trace_test() {
+        let mut regs = Registers::new();
+        let program = Asm::parse_program("set a 3\nsub a 1\njnz a -1\nmul b 2", &mut regs).unwrap();
+        let mut vm = Machine::new(program, regs);
+        let mut tracer = Tracer::with_breakpoints(vec![
+            Breakpoint::At(3),
+            Breakpoint::parse("a == 1").unwrap(),
+        ]);
+
+        let (state, frames) = crate::viz::capture(|| tracer.run(&mut vm, &mut ()));
+        assert_eq!(state, State::Halted);
+
+        // the start, then every step: a short run is drawn in full
+        assert_eq!(frames.len(), 1 + 8);
+
+        // "a == 1" breaks once when it becomes true, not on every step it stays true
+        let breaks: Vec<&String> = frames.iter().filter_map(|f| f.status.get(2)).collect();
+        assert_eq!(breaks, ["breakpoint: a == 1", "breakpoint: instruction 3"]);
+
+        let plain = frames[4].to_plain();
+        assert_eq!(
+            plain,
+            "    0  set a 3       1 █ a 1
+    1  sub a 1       2 █ b 0
+    2  jnz a -1      1 █
+●   3  mul b 2       0 █
+step 4, pc 2, running
+jnz 1  set 1  sub 2
+breakpoint: a == 1
+"
+        );
+
+        // the current instruction is highlighted, and "a" just changed
+        assert_eq!(frames[4].get(7, 2).unwrap().style.bg, Some(Color::Blue));
+        assert_eq!(frames[4].get(25, 0).unwrap().style.bg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn count_test() {
+        assert_eq!(count(12_345), "12345");
+        assert_eq!(count(1_234_567), "1234k");
+        assert_eq!(count(123_456_789), "123M");
+    }
+}
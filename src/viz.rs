@@ -0,0 +1,539 @@
+//! Terminal visualizations, built with the `visualize` feature (`just viz DAY`).
+//!
+//! Solutions describe what to draw as [`Frame`]s and hand them to [`emit`].  Where the frames go
+//! depends on the [`Visualize`] sink installed for the current thread:
+//!
+//!  - the runner installs a [`Terminal`], which animates the frames with ANSI escapes and reads
+//!    space (pause), `n` (step while paused), `+`/`-` (speed) and `q` or Ctrl-C (quit) from the
+//!    keyboard,
+//!  - with `--record`, the runner installs a [`crate::record::Recorder`] instead, which saves them
+//!    to a file,
+//!  - tests use [`capture`], which collects them with a [`Headless`] sink,
+//!  - with no sink installed, [`emit`] does nothing.
+//!
+//! Building a frame isn't free, so solutions should check [`active`] before drawing.
+
+#![cfg(all(feature = "visualize", any(test, not(feature = "no_std"))))]
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// The color as RGB, for renderers that don't speak ANSI.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Self::Black => (0, 0, 0),
+            Self::Red => (205, 49, 49),
+            Self::Green => (13, 188, 121),
+            Self::Yellow => (229, 229, 16),
+            Self::Blue => (36, 114, 200),
+            Self::Magenta => (188, 63, 188),
+            Self::Cyan => (17, 168, 205),
+            Self::White => (229, 229, 229),
+            Self::Gray => (118, 118, 118),
+            Self::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// SGR parameters selecting this color, as a foreground (`base` 30) or background (40).
+    fn sgr(self, base: u8) -> String {
+        match self {
+            Self::Black => format!("{}", base),
+            Self::Red => format!("{}", base + 1),
+            Self::Green => format!("{}", base + 2),
+            Self::Yellow => format!("{}", base + 3),
+            Self::Blue => format!("{}", base + 4),
+            Self::Magenta => format!("{}", base + 5),
+            Self::Cyan => format!("{}", base + 6),
+            Self::White => format!("{}", base + 7),
+            Self::Gray => format!("{}", base + 60),
+            Self::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", base + 8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub strike: bool,
+}
+
+impl Style {
+    pub fn fg(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            ..Self::default()
+        }
+    }
+
+    pub fn bg(color: Color) -> Self {
+        Self {
+            bg: Some(color),
+            ..Self::default()
+        }
+    }
+
+    pub fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    pub fn strike(self) -> Self {
+        Self {
+            strike: true,
+            ..self
+        }
+    }
+
+    /// The escape sequence switching to this style from the default one.
+    fn ansi(&self) -> String {
+        let mut params = vec!["0".to_string()];
+        if self.bold {
+            params.push("1".to_string());
+        }
+        if self.strike {
+            params.push("9".to_string());
+        }
+        if let Some(fg) = self.fg {
+            params.push(fg.sgr(30));
+        }
+        if let Some(bg) = self.bg {
+            params.push(bg.sgr(40));
+        }
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+/// A grid of styled characters, plus some status lines shown underneath.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    pub status: Vec<String>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            status: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        (x < self.width && y < self.height).then(|| &self.cells[y * self.width + x])
+    }
+
+    /// Set one cell.  Anything outside the frame is clipped.
+    pub fn set(&mut self, x: usize, y: usize, ch: char, style: Style) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = Cell { ch, style };
+        }
+    }
+
+    /// Write a string starting at (x, y), clipped at the right edge.
+    pub fn print(&mut self, x: usize, y: usize, text: &str, style: Style) {
+        for (i, ch) in text.chars().enumerate() {
+            self.set(x + i, y, ch, style);
+        }
+    }
+
+    /// Add a line of text below the grid.
+    pub fn status(&mut self, line: impl Into<String>) {
+        self.status.push(line.into());
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1)).take(self.height)
+    }
+
+    /// The frame's characters without any styling, one line per row, then the status lines.
+    pub fn to_plain(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            let line: String = row.iter().map(|cell| cell.ch).collect();
+            out += line.trim_end();
+            out.push('\n');
+        }
+        for line in &self.status {
+            out += line;
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The frame with ANSI styling, each line ending with a clear-to-end-of-line.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            let mut style = Style::default();
+            for cell in row {
+                if cell.style != style {
+                    style = cell.style;
+                    out += &style.ansi();
+                }
+                out.push(cell.ch);
+            }
+            out += "\x1b[0m\x1b[K\n";
+        }
+        for line in &self.status {
+            let _ = writeln!(out, "{line}\x1b[K");
+        }
+        out
+    }
+}
+
+/// Somewhere to send frames.
+pub trait Visualize {
+    fn frame(&mut self, frame: Frame);
+
+    /// Whether frames are still wanted.  Solutions can skip drawing when this is false.
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    /// Hold the next frame until the viewer carries on, e.g. at a breakpoint.  Sinks nobody is
+    /// watching ignore this.
+    fn pause(&mut self) {}
+
+    /// Called once the solution is done, e.g. to save what was collected.
+    fn finish(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Collects frames in memory, for tests.  Clones share the same frames, so one clone can be
+/// installed while another is kept around to read them back.
+#[derive(Debug, Clone, Default)]
+pub struct Headless {
+    frames: Rc<RefCell<Vec<Frame>>>,
+}
+
+impl Headless {
+    /// Remove and return the frames collected so far.
+    pub fn take(&self) -> Vec<Frame> {
+        self.frames.take()
+    }
+}
+
+impl Visualize for Headless {
+    fn frame(&mut self, frame: Frame) {
+        self.frames.borrow_mut().push(frame);
+    }
+}
+
+/// Animates frames in the terminal.  The terminal is only taken over once the first frame
+/// arrives, so days without a visualization print normally.
+pub struct Terminal {
+    /// Delay between frames.
+    interval: Duration,
+    started: bool,
+    paused: bool,
+    quit: bool,
+    /// Keyboard input, if there's a terminal to read from.
+    tty: Option<File>,
+    /// `stty -g` settings to restore on exit.
+    saved_tty: Option<String>,
+    /// Whether the terminal is still taken over, shared with the panic hook that gives it back.
+    taken_over: Arc<AtomicBool>,
+}
+
+impl Terminal {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / fps.max(1),
+            started: false,
+            paused: false,
+            quit: false,
+            tty: None,
+            saved_tty: None,
+            taken_over: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn start(&mut self) {
+        self.started = true;
+        self.saved_tty = stty(&["-g"]);
+        self.tty = self.saved_tty.as_ref().and_then(|_| {
+            // non-canonical and non-blocking, so single key presses can be polled, and without
+            // signals, so Ctrl-C reaches the key handler and the terminal is restored
+            stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "0"])?;
+            File::open("/dev/tty").ok()
+        });
+
+        // a panic doesn't drop the thread's sink, so the hook has to give the terminal back
+        self.taken_over.store(true, Ordering::SeqCst);
+        let taken_over = Arc::clone(&self.taken_over);
+        let saved = self.saved_tty.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if taken_over.swap(false, Ordering::SeqCst) {
+                reset(saved.as_deref());
+            }
+            previous(info);
+        }));
+
+        // switch to the alternate screen and hide the cursor
+        print!("\x1b[?1049h\x1b[?25l");
+    }
+
+    fn fps(&self) -> u32 {
+        (1.0 / self.interval.as_secs_f64()).round() as u32
+    }
+
+    /// Handle any pending key presses.  Returns true if a single step was requested.
+    fn poll_keys(&mut self) -> bool {
+        let mut step = false;
+        let mut buf = [0; 16];
+
+        let n = self
+            .tty
+            .as_mut()
+            .and_then(|tty| tty.read(&mut buf).ok())
+            .unwrap_or(0);
+
+        for &key in &buf[..n] {
+            match key {
+                b' ' => self.paused = !self.paused,
+                b'n' | b'.' => step = true,
+                b'+' | b'=' => self.interval = (self.interval / 2).max(Duration::from_millis(1)),
+                b'-' | b'_' => self.interval = (self.interval * 2).min(Duration::from_secs(2)),
+                b'q' | 0x03 => self.quit = true,
+                _ => {}
+            }
+        }
+
+        step
+    }
+
+    fn restore(&mut self) {
+        self.taken_over.store(false, Ordering::SeqCst);
+        reset(self.saved_tty.take().as_deref());
+        self.tty = None;
+    }
+}
+
+impl Visualize for Terminal {
+    fn frame(&mut self, frame: Frame) {
+        if self.quit {
+            return;
+        }
+        if !self.started {
+            self.start();
+        }
+
+        let state = if self.paused { "paused" } else { "playing" };
+        let mut out = format!("\x1b[H{}", frame.to_ansi());
+        let _ = write!(
+            out,
+            "\n{state} at {} fps | space: pause  n: step  +/-: speed  q: quit\x1b[K\x1b[J",
+            self.fps()
+        );
+        print!("{out}");
+        let _ = std::io::stdout().flush();
+
+        // wait out the frame, or until unpaused or stepped
+        let shown = Instant::now();
+        loop {
+            let step = self.poll_keys();
+            if self.quit {
+                self.restore();
+                return;
+            }
+            if (self.paused && step) || (!self.paused && shown.elapsed() >= self.interval) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(5).min(self.interval));
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        !self.quit
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.started && !self.quit {
+            self.restore();
+        }
+    }
+}
+
+/// Leave the alternate screen with the cursor showing, and put back the `stty -g` settings.
+fn reset(saved_tty: Option<&str>) {
+    print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+    let _ = std::io::stdout().flush();
+
+    if let Some(saved) = saved_tty {
+        stty(&[saved.trim()]);
+    }
+}
+
+/// Run `stty` against the controlling terminal, returning its output.
+fn stty(args: &[&str]) -> Option<String> {
+    let tty = File::open("/dev/tty").ok()?;
+    let out = Command::new("stty")
+        .args(args)
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+thread_local! {
+    static SINK: RefCell<Option<Box<dyn Visualize>>> = RefCell::new(None);
+}
+
+/// Send frames emitted on this thread to `sink`.
+pub fn install(sink: impl Visualize + 'static) {
+    SINK.with(|s| *s.borrow_mut() = Some(Box::new(sink)));
+}
+
+/// Remove this thread's sink, e.g. to restore the terminal before printing answers.
+pub fn uninstall() -> Option<Box<dyn Visualize>> {
+    SINK.with(|s| s.borrow_mut().take())
+}
+
+/// Whether a sink is installed and still wants frames.
+pub fn active() -> bool {
+    SINK.with(|s| s.borrow().as_ref().is_some_and(|sink| sink.is_active()))
+}
+
+/// Ask this thread's sink to pause before showing the next frame.
+pub fn pause() {
+    SINK.with(|s| {
+        if let Some(sink) = s.borrow_mut().as_mut() {
+            sink.pause();
+        }
+    });
+}
+
+/// Send a frame to this thread's sink, if there is one.
+pub fn emit(frame: Frame) {
+    SINK.with(|s| {
+        if let Some(sink) = s.borrow_mut().as_mut() {
+            sink.frame(frame);
+        }
+    });
+}
+
+/// Run `f` with a [`Headless`] sink installed, returning its result and the frames it emitted.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Frame>) {
+    let headless = Headless::default();
+    let previous = SINK.with(|s| s.replace(Some(Box::new(headless.clone()))));
+
+    let out = f();
+
+    SINK.with(|s| s.replace(previous));
+    (out, headless.take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_test() {
+        let mut frame = Frame::new(4, 2);
+        frame.print(0, 0, "ab", Style::default());
+        frame.set(3, 1, '#', Style::fg(Color::Red));
+        frame.set(9, 9, '!', Style::default());
+        frame.status("step 1");
+
+        assert_eq!(frame.to_plain(), "ab\n   #\nstep 1\n");
+        assert_eq!(frame.get(3, 1).unwrap().style.fg, Some(Color::Red));
+        assert_eq!(frame.get(4, 0), None);
+    }
+
+    #[test]
+    fn ansi_test() {
+        let mut frame = Frame::new(3, 1);
+        frame.print(0, 0, "ab", Style::fg(Color::Green).bold());
+        frame.set(2, 0, 'c', Style::bg(Color::Rgb(1, 2, 3)).strike());
+
+        assert_eq!(
+            frame.to_ansi(),
+            "\x1b[0;1;32mab\x1b[0;9;48;2;1;2;3mc\x1b[0m\x1b[K\n"
+        );
+    }
+
+    #[test]
+    fn capture_test() {
+        assert!(!active());
+
+        let (answer, frames) = capture(|| {
+            assert!(active());
+            for i in 0..3 {
+                let mut frame = Frame::new(1, 1);
+                frame.set(0, 0, char::from_digit(i, 10).unwrap(), Style::default());
+                emit(frame);
+            }
+            42
+        });
+
+        assert_eq!(answer, 42);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].to_plain(), "2\n");
+        assert!(!active());
+
+        // without a sink, frames go nowhere
+        emit(Frame::new(1, 1));
+    }
+}
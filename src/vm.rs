@@ -0,0 +1,645 @@
+//! A small register machine shared by the assembly-flavored days (8, 18, 23).
+//!
+//! Each day describes its own instructions by implementing [`Instruction`], and the [`Machine`]
+//! takes care of the program counter, registers, I/O hooks and execution statistics.  The
+//! set/add/sub/mul/mod/jgz/jnz/snd/rcv dialect used by days 18 and 23 is built in as [`Asm`].
+
+#![cfg(any(test, not(feature = "no_std")))]
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// A handle to a register, obtained from [`Registers::intern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reg(usize);
+
+/// A register file holding named (`"foo"`) or lettered (`'a'`) registers.
+///
+/// Register names are interned into dense slots while parsing, so executing an instruction
+/// never hashes a name.  Every register starts at zero.
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    names: Vec<String>,
+    slots: HashMap<String, Reg>,
+    values: Vec<i64>,
+}
+
+impl Registers {
+    /// Create an empty register file whose registers are added as they are interned.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a register file with the registers `a` through `z` already interned, in order.
+    pub fn lettered() -> Self {
+        let mut regs = Self::new();
+        for c in 'a'..='z' {
+            regs.intern(&c.to_string());
+        }
+        regs
+    }
+
+    /// Look up a register by name, adding it if it doesn't exist yet.
+    pub fn intern(&mut self, name: &str) -> Reg {
+        if let Some(&reg) = self.slots.get(name) {
+            return reg;
+        }
+
+        let reg = Reg(self.values.len());
+        self.names.push(name.to_string());
+        self.slots.insert(name.to_string(), reg);
+        self.values.push(0);
+        reg
+    }
+
+    pub fn get(&self, reg: Reg) -> i64 {
+        self.values[reg.0]
+    }
+
+    pub fn set(&mut self, reg: Reg, value: i64) {
+        self.values[reg.0] = value;
+    }
+
+    /// Get a register's value by name, if it has been interned.
+    pub fn by_name(&self, name: &str) -> Option<i64> {
+        self.slots.get(name).map(|&reg| self.get(reg))
+    }
+
+    pub fn name(&self, reg: Reg) -> &str {
+        &self.names[reg.0]
+    }
+
+    /// All registers as `(name, value)` pairs, in the order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i64)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(self.values.iter().copied())
+    }
+
+    /// The highest value held by any register.
+    pub fn max(&self) -> Option<i64> {
+        self.values.iter().copied().max()
+    }
+
+    /// Number of registers.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// An instruction argument that is either a register or a literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Reg(Reg),
+    Imm(i64),
+}
+
+impl Operand {
+    /// Parse a literal integer, or intern anything else as a register name.
+    pub fn parse(token: &str, regs: &mut Registers) -> Self {
+        match token.parse() {
+            Ok(value) => Self::Imm(value),
+            Err(_) => Self::Reg(regs.intern(token)),
+        }
+    }
+
+    pub fn value(&self, regs: &Registers) -> i64 {
+        match *self {
+            Self::Reg(reg) => regs.get(reg),
+            Self::Imm(value) => value,
+        }
+    }
+
+    /// The operand as it would be written in the program.
+    pub fn describe(&self, regs: &Registers) -> String {
+        match *self {
+            Self::Reg(reg) => regs.name(reg).to_string(),
+            Self::Imm(value) => value.to_string(),
+        }
+    }
+}
+
+/// A comparison between two values, as in day 8's conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Cmp {
+    /// Parse an operator such as `<=` or `!=`.
+    #[rustfmt::skip]
+    pub fn parse(op: &str) -> Option<Self> {
+        match op {
+            "<"  => Some(Self::Lt),
+            "<=" => Some(Self::Le),
+            ">"  => Some(Self::Gt),
+            ">=" => Some(Self::Ge),
+            "==" => Some(Self::Eq),
+            "!=" => Some(Self::Ne),
+            _    => None,
+        }
+    }
+
+    pub fn eval(self, a: i64, b: i64) -> bool {
+        match self {
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+        }
+    }
+}
+
+/// What the machine should do after an instruction executes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Continue with the next instruction.
+    Next,
+    /// Move the program counter by a relative offset.
+    Jump(i64),
+    /// Stay on this instruction and retry it later (e.g. waiting for input).
+    Block,
+    /// Stop the machine.
+    Halt,
+}
+
+/// The machine's condition after a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    Blocked,
+    Halted,
+}
+
+/// I/O hooks for output (`snd`) and input (`rcv`) instructions.
+pub trait Io {
+    /// Called when the program outputs a value.
+    fn send(&mut self, value: i64);
+
+    /// Called when the program asks for input, with the current value of the destination.
+    /// Returning `None` blocks the machine until input is available.
+    fn receive(&mut self, current: i64) -> Option<i64>;
+}
+
+/// No I/O at all: output is discarded and input blocks forever.
+impl Io for () {
+    fn send(&mut self, _value: i64) {}
+
+    fn receive(&mut self, _current: i64) -> Option<i64> {
+        None
+    }
+}
+
+/// Queue-backed I/O, for connecting machines to each other (day 18 part 2).
+#[derive(Debug, Clone, Default)]
+pub struct Queue {
+    pub input: VecDeque<i64>,
+    pub output: VecDeque<i64>,
+    /// Number of values ever sent.
+    pub sent: usize,
+}
+
+impl Io for Queue {
+    fn send(&mut self, value: i64) {
+        self.output.push_back(value);
+        self.sent += 1;
+    }
+
+    fn receive(&mut self, _current: i64) -> Option<i64> {
+        self.input.pop_front()
+    }
+}
+
+/// A single instruction of some instruction set.
+pub trait Instruction {
+    /// The instruction's mnemonic, used to key the execution counters.
+    fn opcode(&self) -> &'static str;
+
+    /// Execute the instruction against the registers and I/O hooks.
+    fn exec(&self, regs: &mut Registers, io: &mut dyn Io) -> Flow;
+
+    /// The instruction as source code, for program listings.
+    fn describe(&self, regs: &Registers) -> String {
+        let _ = regs;
+        self.opcode().to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine<I> {
+    pub program: Vec<I>,
+    pub registers: Registers,
+    /// Index of the next instruction to execute.
+    pub pc: i64,
+    /// Number of instructions executed so far.
+    pub steps: u64,
+    /// Executions of each instruction, by program index.
+    hits: Vec<u64>,
+    /// Executions of each opcode.
+    counts: BTreeMap<&'static str, u64>,
+    halted: bool,
+}
+
+impl<I: Instruction> Machine<I> {
+    pub fn new(program: Vec<I>, registers: Registers) -> Self {
+        Self {
+            hits: vec![0; program.len()],
+            program,
+            registers,
+            pc: 0,
+            steps: 0,
+            counts: BTreeMap::new(),
+            halted: false,
+        }
+    }
+
+    /// The instruction the program counter points at, if it's inside the program.
+    pub fn current(&self) -> Option<&I> {
+        usize::try_from(self.pc)
+            .ok()
+            .and_then(|pc| self.program.get(pc))
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted || self.current().is_none()
+    }
+
+    /// Execute a single instruction.
+    pub fn step(&mut self, io: &mut dyn Io) -> State {
+        if self.is_halted() {
+            return State::Halted;
+        }
+
+        let pc = self.pc as usize;
+        let inst = &self.program[pc];
+        let flow = inst.exec(&mut self.registers, io);
+
+        if flow == Flow::Block {
+            return State::Blocked;
+        }
+
+        self.steps += 1;
+        self.hits[pc] += 1;
+        *self.counts.entry(inst.opcode()).or_default() += 1;
+
+        match flow {
+            Flow::Next => self.pc += 1,
+            // far enough either way is off the program, and halts it
+            Flow::Jump(offset) => self.pc = self.pc.saturating_add(offset),
+            Flow::Halt => self.halted = true,
+            Flow::Block => unreachable!(),
+        }
+
+        if self.is_halted() {
+            State::Halted
+        } else {
+            State::Running
+        }
+    }
+
+    /// Run until the machine halts or blocks.
+    pub fn run(&mut self, io: &mut dyn Io) -> State {
+        self.run_until(io, |_| false)
+    }
+
+    /// Run until the machine halts or blocks, or `stop` returns true after a step.
+    pub fn run_until(&mut self, io: &mut dyn Io, mut stop: impl FnMut(&Self) -> bool) -> State {
+        loop {
+            let state = self.step(io);
+            if state != State::Running || stop(self) {
+                return state;
+            }
+        }
+    }
+
+    /// Number of times an opcode has been executed.
+    pub fn count(&self, opcode: &str) -> u64 {
+        self.counts.get(opcode).copied().unwrap_or(0)
+    }
+
+    /// Execution counts of every opcode executed so far, sorted by opcode.
+    pub fn counts(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.counts.iter().map(|(&op, &n)| (op, n))
+    }
+
+    /// Execution counts of each instruction, by program index.
+    pub fn hits(&self) -> &[u64] {
+        &self.hits
+    }
+}
+
+/// The instruction set of days 18 and 23.  Arithmetic wraps on overflow, and a `mod` by zero
+/// halts the machine, since there's no sensible value to leave behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Asm {
+    Set(Reg, Operand),
+    Add(Reg, Operand),
+    Sub(Reg, Operand),
+    Mul(Reg, Operand),
+    Mod(Reg, Operand),
+    Jgz(Operand, Operand),
+    Jnz(Operand, Operand),
+    Snd(Operand),
+    Rcv(Reg),
+}
+
+impl Asm {
+    /// Parse one line of assembly, interning its registers.
+    pub fn parse(line: &str, regs: &mut Registers) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let opcode = words.next().ok_or("empty instruction")?;
+        let mut arg = || {
+            words
+                .next()
+                .ok_or_else(|| format!("missing argument in \"{line}\""))
+        };
+
+        let reg = |token: &str, regs: &mut Registers| match Operand::parse(token, regs) {
+            Operand::Reg(reg) => Ok(reg),
+            Operand::Imm(_) => Err(format!("expected a register in \"{line}\"")),
+        };
+
+        Ok(match opcode {
+            "set" => Self::Set(reg(arg()?, regs)?, Operand::parse(arg()?, regs)),
+            "add" => Self::Add(reg(arg()?, regs)?, Operand::parse(arg()?, regs)),
+            "sub" => Self::Sub(reg(arg()?, regs)?, Operand::parse(arg()?, regs)),
+            "mul" => Self::Mul(reg(arg()?, regs)?, Operand::parse(arg()?, regs)),
+            "mod" => Self::Mod(reg(arg()?, regs)?, Operand::parse(arg()?, regs)),
+            "jgz" => Self::Jgz(Operand::parse(arg()?, regs), Operand::parse(arg()?, regs)),
+            "jnz" => Self::Jnz(Operand::parse(arg()?, regs), Operand::parse(arg()?, regs)),
+            "snd" => Self::Snd(Operand::parse(arg()?, regs)),
+            "rcv" => Self::Rcv(reg(arg()?, regs)?),
+            _ => return Err(format!("unrecognized opcode \"{opcode}\"")),
+        })
+    }
+
+    /// Parse a whole program, one instruction per line.
+    pub fn parse_program(input: &str, regs: &mut Registers) -> Result<Vec<Self>, String> {
+        input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Self::parse(line, regs))
+            .collect()
+    }
+}
+
+impl Instruction for Asm {
+    fn opcode(&self) -> &'static str {
+        match self {
+            Self::Set(..) => "set",
+            Self::Add(..) => "add",
+            Self::Sub(..) => "sub",
+            Self::Mul(..) => "mul",
+            Self::Mod(..) => "mod",
+            Self::Jgz(..) => "jgz",
+            Self::Jnz(..) => "jnz",
+            Self::Snd(..) => "snd",
+            Self::Rcv(..) => "rcv",
+        }
+    }
+
+    fn exec(&self, regs: &mut Registers, io: &mut dyn Io) -> Flow {
+        match *self {
+            Self::Set(x, y) => regs.set(x, y.value(regs)),
+            Self::Add(x, y) => regs.set(x, regs.get(x).wrapping_add(y.value(regs))),
+            Self::Sub(x, y) => regs.set(x, regs.get(x).wrapping_sub(y.value(regs))),
+            Self::Mul(x, y) => regs.set(x, regs.get(x).wrapping_mul(y.value(regs))),
+            Self::Mod(_, y) if y.value(regs) == 0 => return Flow::Halt,
+            Self::Mod(x, y) => regs.set(x, regs.get(x).wrapping_rem_euclid(y.value(regs))),
+            Self::Jgz(x, y) if x.value(regs) > 0 => return Flow::Jump(y.value(regs)),
+            Self::Jnz(x, y) if x.value(regs) != 0 => return Flow::Jump(y.value(regs)),
+            Self::Jgz(..) | Self::Jnz(..) => {}
+            Self::Snd(x) => io.send(x.value(regs)),
+            Self::Rcv(x) => match io.receive(regs.get(x)) {
+                Some(value) => regs.set(x, value),
+                None => return Flow::Block,
+            },
+        }
+
+        Flow::Next
+    }
+
+    fn describe(&self, regs: &Registers) -> String {
+        let args = match *self {
+            Self::Set(x, y)
+            | Self::Add(x, y)
+            | Self::Sub(x, y)
+            | Self::Mul(x, y)
+            | Self::Mod(x, y) => {
+                format!("{} {}", regs.name(x), y.describe(regs))
+            }
+            Self::Jgz(x, y) | Self::Jnz(x, y) => {
+                format!("{} {}", x.describe(regs), y.describe(regs))
+            }
+            Self::Snd(x) => x.describe(regs),
+            Self::Rcv(x) => regs.name(x).to_string(),
+        };
+
+        format!("{} {args}", self.opcode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Day 8's conditional increment, as an example of a day-specific instruction set.
+    struct Inc {
+        reg: Reg,
+        amount: i64,
+        cond_reg: Reg,
+        cond: Cmp,
+        value: i64,
+    }
+
+    impl Instruction for Inc {
+        fn opcode(&self) -> &'static str {
+            "inc"
+        }
+
+        fn exec(&self, regs: &mut Registers, _io: &mut dyn Io) -> Flow {
+            if self.cond.eval(regs.get(self.cond_reg), self.value) {
+                regs.set(self.reg, regs.get(self.reg) + self.amount);
+            }
+            Flow::Next
+        }
+    }
+
+    #[test]
+    fn named_registers_test() {
+        let mut regs = Registers::new();
+        let (a, b, c) = (regs.intern("a"), regs.intern("b"), regs.intern("c"));
+
+        // b inc 5 if a > 1
+        // a inc 1 if b < 5
+        // c dec -10 if a >= 1
+        // c inc -20 if c == 10
+        #[rustfmt::skip]
+        let program = vec![
+            Inc { reg: b, amount: 5,   cond_reg: a, cond: Cmp::Gt, value: 1 },
+            Inc { reg: a, amount: 1,   cond_reg: b, cond: Cmp::Lt, value: 5 },
+            Inc { reg: c, amount: 10,  cond_reg: a, cond: Cmp::Ge, value: 1 },
+            Inc { reg: c, amount: -20, cond_reg: c, cond: Cmp::Eq, value: 10 },
+        ];
+
+        let mut vm = Machine::new(program, regs);
+        let mut highest = 0;
+        while vm.step(&mut ()) == State::Running {
+            highest = highest.max(vm.registers.max().unwrap());
+        }
+
+        assert_eq!(vm.registers.max(), Some(1));
+        assert_eq!(vm.registers.by_name("c"), Some(-10));
+        assert_eq!(highest, 10);
+        assert_eq!(vm.count("inc"), 4);
+    }
+
+    /// Day 18 part 1's sound card: `rcv` with a non-zero register recovers the last sound.
+    #[derive(Default)]
+    struct Sound {
+        last: i64,
+        recovered: Option<i64>,
+    }
+
+    impl Io for Sound {
+        fn send(&mut self, value: i64) {
+            self.last = value;
+        }
+
+        fn receive(&mut self, current: i64) -> Option<i64> {
+            if current != 0 {
+                self.recovered = Some(self.last);
+            }
+            Some(current)
+        }
+    }
+
+    #[test]
+    fn sound_test() {
+        let mut regs = Registers::lettered();
+        let program = Asm::parse_program(
+            "set a 1\nadd a 2\nmul a a\nmod a 5\nsnd a\nset a 0\nrcv a\njgz a -1\nset a 1\njgz a -2",
+            &mut regs,
+        )
+        .unwrap();
+
+        let mut vm = Machine::new(program, regs);
+        let mut sound = Sound::default();
+        while sound.recovered.is_none() {
+            vm.step(&mut sound);
+        }
+
+        assert_eq!(sound.recovered, Some(4));
+        assert_eq!(vm.count("rcv"), 2);
+        assert_eq!(vm.count("jgz"), 3);
+        assert_eq!(vm.hits()[7], 2);
+    }
+
+    #[test]
+    fn queue_test() {
+        let source = "snd 1\nsnd 2\nsnd p\nrcv a\nrcv b\nrcv c\nrcv d";
+        let mut vms: Vec<_> = (0..2)
+            .map(|id| {
+                let mut regs = Registers::lettered();
+                let program = Asm::parse_program(source, &mut regs).unwrap();
+                let p = regs.intern("p");
+                regs.set(p, id);
+                Machine::new(program, regs)
+            })
+            .collect();
+        let mut queues = [Queue::default(), Queue::default()];
+
+        // run both programs until neither can make progress
+        loop {
+            let states = [vms[0].run(&mut queues[0]), vms[1].run(&mut queues[1])];
+            let (a, b) = queues.split_at_mut(1);
+            a[0].input.extend(b[0].output.drain(..));
+            b[0].input.extend(a[0].output.drain(..));
+
+            let stuck = |(&state, queue): (&State, &Queue)| {
+                state == State::Halted || (state == State::Blocked && queue.input.is_empty())
+            };
+            if states.iter().zip(&queues).all(stuck) {
+                break;
+            }
+        }
+
+        assert_eq!(queues[1].sent, 3);
+        assert_eq!(vms[0].registers.by_name("c"), Some(1));
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        let mut regs = Registers::lettered();
+
+        assert!(Asm::parse("nop a", &mut regs).is_err());
+        assert!(Asm::parse("set a", &mut regs).is_err());
+        assert!(Asm::parse("set 1 a", &mut regs).is_err());
+        assert_eq!(
+            Asm::parse("jnz 1 -2", &mut regs),
+            Ok(Asm::Jnz(Operand::Imm(1), Operand::Imm(-2)))
+        );
+        assert_eq!(
+            Asm::parse("mod  a   b", &mut regs).unwrap().describe(&regs),
+            "mod a b"
+        );
+    }
+
+    #[test]
+    fn halt_test() {
+        let mut regs = Registers::lettered();
+        let program = Asm::parse_program("set a 3\nsub a 1\njnz a -1\nmul b 2", &mut regs).unwrap();
+        let mut vm = Machine::new(program, regs);
+
+        assert_eq!(vm.run(&mut ()), State::Halted);
+        assert_eq!(vm.steps, 8);
+        assert_eq!(
+            vm.counts().collect::<Vec<_>>(),
+            [("jnz", 3), ("mul", 1), ("set", 1), ("sub", 3)]
+        );
+        assert_eq!(vm.step(&mut ()), State::Halted);
+    }
+
+    #[test]
+    fn overflow_test() {
+        let mut regs = Registers::lettered();
+        let source = "set a 9223372036854775807\nadd a 1\nmul b a\nsub b 1\nmod b -1\n\
+                      jnz 1 9223372036854775807";
+        let program = Asm::parse_program(source, &mut regs).unwrap();
+        let mut vm = Machine::new(program, regs);
+
+        assert_eq!(vm.run(&mut ()), State::Halted);
+        assert_eq!(vm.registers.by_name("a"), Some(i64::MIN));
+        assert_eq!(vm.registers.by_name("b"), Some(0));
+        assert_eq!(vm.steps, 6);
+
+        // dividing by zero stops the program where it is
+        let mut regs = Registers::lettered();
+        let program = Asm::parse_program("set a 7\nmod a b\nset a 1", &mut regs).unwrap();
+        let mut vm = Machine::new(program, regs);
+        assert_eq!(vm.run(&mut ()), State::Halted);
+        assert_eq!((vm.pc, vm.registers.by_name("a")), (1, Some(7)));
+    }
+}
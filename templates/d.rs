@@ -1,6 +1,8 @@
 //! A solution to day $AOC_DAY year $AOC_YEAR.
 //! https://adventofcode.com/$AOC_YEAR/day/$AOC_DAY
 
+#![cfg(any(test, not(feature = "no_std")))]
+
 type Model = u8;
 type Answer = String;
 